@@ -0,0 +1,71 @@
+//! Measures the time and number of allocations needed to create errors
+//!
+//! Run with `cargo bench --bench wrap`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn bench(name: &str, f: impl Fn() -> ees::Error) {
+    const ITERATIONS: u32 = 100_000;
+    drop(f());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        drop(black_box(f()));
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{:<32} {:>8.1} ns/iter {:>6.1} allocations/iter",
+        name,
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS),
+        allocations as f64 / f64::from(ITERATIONS),
+    );
+}
+
+fn main() {
+    bench("err!(literal)", || ees::err!("root cause").into());
+    bench("err!(format)", || {
+        ees::err!("code {}", black_box(42)).into()
+    });
+    bench("wrap!(io::Error)", || {
+        let error = std::io::Error::other("root cause");
+        ees::wrap!(error, "failed to read file").into()
+    });
+    bench("wrap!(ees::Error) short x10", || {
+        let mut error: ees::Error = ees::err!("root cause").into();
+        for depth in 0..10 {
+            error = ees::wrap!(error, "layer {}", depth).into();
+        }
+        error
+    });
+    bench("wrap!(ees::Error) long x10", || {
+        let mut error: ees::Error = ees::err!("root cause").into();
+        for depth in 0..10 {
+            error = ees::wrap!(error, "while processing layer {}", depth).into();
+        }
+        error
+    });
+}
@@ -0,0 +1,390 @@
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+
+use crate::{is_ident, is_punct, Error};
+
+pub(crate) fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    let item = parse_item(input)?;
+    let mut output = String::new();
+    output.push_str(&display_impl(&item)?);
+    output.push_str(&error_impl(&item));
+    for variant in &item.variants {
+        output.push_str(&from_impl(&item.name, variant)?);
+    }
+    Ok(crate::parse(&output))
+}
+
+struct Item {
+    name: String,
+    variants: Vec<Variant>,
+}
+
+/// For structs, there is exactly one variant without a name
+struct Variant {
+    name: Option<String>,
+    span: Span,
+    attrs: Attrs,
+    style: Style,
+    fields: Vec<Field>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Named,
+    Tuple,
+    Unit,
+}
+
+struct Field {
+    binding: String,
+    ty: String,
+    attrs: Attrs,
+}
+
+#[derive(Default)]
+struct Attrs {
+    message: Option<Message>,
+    source: bool,
+    from: bool,
+}
+
+enum Message {
+    Format(Vec<TokenTree>),
+    Transparent,
+}
+
+fn parse_item(input: TokenStream) -> Result<Item, Error> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+    let attrs = parse_attrs(&tokens, &mut i)?;
+    skip_visibility(&tokens, &mut i);
+    let is_enum = match tokens.get(i) {
+        Some(token) if is_ident(token, "enum") => true,
+        Some(token) if is_ident(token, "struct") => false,
+        _ => {
+            return Err((
+                Span::call_site(),
+                "`ees::Derive` can only be used on structs and enums",
+            ))
+        }
+    };
+    let (name, span) = match tokens.get(i + 1) {
+        Some(TokenTree::Ident(name)) => (name.to_string(), name.span()),
+        _ => return Err((Span::call_site(), "expected a type name")),
+    };
+    i += 2;
+    match tokens.get(i) {
+        Some(token) if is_punct(token, '<') => {
+            return Err((token.span(), "`ees::Derive` does not support generic types"))
+        }
+        Some(token) if is_ident(token, "where") => {
+            return Err((token.span(), "`ees::Derive` does not support generic types"))
+        }
+        _ => (),
+    }
+
+    let variants = if is_enum {
+        let body = match tokens.get(i) {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => group,
+            _ => return Err((span, "expected enum variants")),
+        };
+        if attrs.message.is_some() {
+            return Err((span, "`#[error]` must be placed on each enum variant"));
+        }
+        split_commas(body.stream())
+            .into_iter()
+            .map(|tokens| parse_variant(&tokens))
+            .collect::<Result<_, _>>()?
+    } else {
+        let (style, fields) = parse_fields(tokens.get(i))?;
+        vec![Variant {
+            name: None,
+            span,
+            attrs,
+            style,
+            fields,
+        }]
+    };
+    Ok(Item { name, variants })
+}
+
+fn parse_variant(tokens: &[TokenTree]) -> Result<Variant, Error> {
+    let mut i = 0;
+    let attrs = parse_attrs(tokens, &mut i)?;
+    let (name, span) = match tokens.get(i) {
+        Some(TokenTree::Ident(name)) => (name.to_string(), name.span()),
+        _ => return Err((Span::call_site(), "expected a variant name")),
+    };
+    let (style, fields) = parse_fields(tokens.get(i + 1))?;
+    Ok(Variant {
+        name: Some(name),
+        span,
+        attrs,
+        style,
+        fields,
+    })
+}
+
+fn parse_fields(token: Option<&TokenTree>) -> Result<(Style, Vec<Field>), Error> {
+    let (style, group) = match token {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            (Style::Named, group)
+        }
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            (Style::Tuple, group)
+        }
+        _ => return Ok((Style::Unit, Vec::new())),
+    };
+    let fields = split_commas(group.stream())
+        .into_iter()
+        .enumerate()
+        .map(|(index, tokens)| {
+            let mut i = 0;
+            let attrs = parse_attrs(&tokens, &mut i)?;
+            skip_visibility(&tokens, &mut i);
+            let binding = if style == Style::Named {
+                let name = tokens[i].to_string();
+                i += 2;
+                name
+            } else {
+                format!("_{}", index)
+            };
+            let ty: TokenStream = tokens[i..].iter().cloned().collect();
+            Ok(Field {
+                binding,
+                ty: ty.to_string(),
+                attrs,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((style, fields))
+}
+
+fn parse_attrs(tokens: &[TokenTree], i: &mut usize) -> Result<Attrs, Error> {
+    let mut attrs = Attrs::default();
+    while let (Some(hash), Some(TokenTree::Group(group))) = (tokens.get(*i), tokens.get(*i + 1)) {
+        if !is_punct(hash, '#') || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        let attr: Vec<TokenTree> = group.stream().into_iter().collect();
+        match attr.first() {
+            Some(token) if is_ident(token, "source") => attrs.source = true,
+            Some(token) if is_ident(token, "from") => attrs.from = true,
+            Some(token) if is_ident(token, "error") => {
+                let args = match attr.get(1) {
+                    Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => {
+                        args
+                    }
+                    _ => return Err((token.span(), "expected `#[error(\"...\")]`")),
+                };
+                let args: Vec<TokenTree> = args.stream().into_iter().collect();
+                attrs.message = match args.as_slice() {
+                    [token] if is_ident(token, "transparent") => Some(Message::Transparent),
+                    [TokenTree::Literal(_), ..] => Some(Message::Format(args)),
+                    _ => return Err((token.span(), "expected a format string")),
+                };
+            }
+            _ => (),
+        }
+        *i += 2;
+    }
+    Ok(attrs)
+}
+
+fn skip_visibility(tokens: &[TokenTree], i: &mut usize) {
+    if tokens.get(*i).is_some_and(|token| is_ident(token, "pub")) {
+        *i += 1;
+        if let Some(TokenTree::Group(group)) = tokens.get(*i) {
+            if group.delimiter() == Delimiter::Parenthesis {
+                *i += 1;
+            }
+        }
+    }
+}
+
+/// Split on top-level commas, taking care of commas within generic arguments
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut result = vec![Vec::new()];
+    let mut depth = 0;
+    let mut previous_dash = false;
+    for token in stream {
+        let mut dash = false;
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    result.push(Vec::new());
+                    continue;
+                }
+                '<' => depth += 1,
+                // `->` in e.g. `fn() -> T` does not close an angle bracket
+                '>' if !previous_dash => depth -= 1,
+                '-' => dash = true,
+                _ => (),
+            }
+        }
+        previous_dash = dash;
+        result.last_mut().unwrap().push(token);
+    }
+    result.retain(|tokens| !tokens.is_empty());
+    result
+}
+
+impl Variant {
+    fn path(&self) -> String {
+        match &self.name {
+            Some(name) => format!("Self::{}", name),
+            None => "Self".to_string(),
+        }
+    }
+
+    fn pattern(&self) -> String {
+        let bindings: Vec<&str> = self
+            .fields
+            .iter()
+            .map(|field| field.binding.as_str())
+            .collect();
+        match self.style {
+            Style::Named => format!("{} {{ {} }}", self.path(), bindings.join(", ")),
+            Style::Tuple => format!("{}({})", self.path(), bindings.join(", ")),
+            Style::Unit => self.path(),
+        }
+    }
+
+    fn source_field(&self) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|field| field.attrs.source || field.attrs.from)
+            .or_else(|| self.fields.iter().find(|field| field.binding == "source"))
+    }
+
+    fn transparent_field(&self) -> Result<&Field, Error> {
+        match self.fields.as_slice() {
+            [field] => Ok(field),
+            _ => Err((
+                self.span,
+                "`#[error(transparent)]` requires exactly one field",
+            )),
+        }
+    }
+}
+
+fn display_impl(item: &Item) -> Result<String, Error> {
+    let mut arms = String::new();
+    for variant in &item.variants {
+        let body = match &variant.attrs.message {
+            None => return Err((variant.span, "missing `#[error(\"...\")]` attribute")),
+            Some(Message::Transparent) => format!(
+                "::core::fmt::Display::fmt({}, __ees_formatter)",
+                variant.transparent_field()?.binding
+            ),
+            Some(Message::Format(args)) => {
+                let format = positional_to_bindings(&args[0].to_string());
+                let rest: TokenStream = args[1..].iter().cloned().collect();
+                format!("::core::write!(__ees_formatter, {}{})", format, rest)
+            }
+        };
+        arms.push_str(&format!("{} => {{ {} }}\n", variant.pattern(), body));
+    }
+    Ok(format!(
+        "impl ::core::fmt::Display for {name} {{
+            #[allow(unused_variables)]
+            // the fields are bound by name, so the formatter needs a name that
+            // can't be used by a field
+            fn fmt(
+                &self,
+                __ees_formatter: &mut ::core::fmt::Formatter<'_>,
+            ) -> ::core::fmt::Result {{
+                {body}
+            }}
+        }}",
+        name = item.name,
+        body = match_self(item, &arms),
+    ))
+}
+
+fn match_self(item: &Item, arms: &str) -> String {
+    if item.variants.is_empty() {
+        // `match self {}` is rejected for references to empty enums
+        "match *self {}".to_string()
+    } else {
+        format!("match self {{ {} }}", arms)
+    }
+}
+
+/// Rewrite `{0}` to `{_0}`, so that tuple fields can be referred to by index
+fn positional_to_bindings(format: &str) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        result.push(c);
+        if c == '{' {
+            match chars.peek() {
+                Some('{') => result.push(chars.next().unwrap()),
+                Some(c) if c.is_ascii_digit() => result.push('_'),
+                _ => (),
+            }
+        }
+    }
+    result
+}
+
+fn error_impl(item: &Item) -> String {
+    let mut arms = String::new();
+    for variant in &item.variants {
+        let body = match (&variant.attrs.message, variant.source_field()) {
+            (Some(Message::Transparent), _) => match variant.transparent_field() {
+                Ok(field) => format!(
+                    "(&::ees::internal::SourceWrap({})).ees_source()
+                        .and_then(::core::error::Error::source)",
+                    field.binding
+                ),
+                // already reported by the `Display` impl
+                Err(_) => "::core::option::Option::None".to_string(),
+            },
+            (_, Some(field)) => format!(
+                "(&::ees::internal::SourceWrap({})).ees_source()",
+                field.binding
+            ),
+            (_, None) => "::core::option::Option::None".to_string(),
+        };
+        arms.push_str(&format!("{} => {{ {} }}\n", variant.pattern(), body));
+    }
+    format!(
+        "impl ::core::error::Error for {name} {{
+            #[allow(unused_variables)]
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {{
+                #[allow(unused_imports)]
+                use ::ees::internal::{{BoxedSource as _, ErrorSource as _}};
+                {body}
+            }}
+        }}",
+        name = item.name,
+        body = match_self(item, &arms),
+    )
+}
+
+fn from_impl(name: &str, variant: &Variant) -> Result<String, Error> {
+    let field = match variant.fields.iter().find(|field| field.attrs.from) {
+        Some(field) => field,
+        None => return Ok(String::new()),
+    };
+    if variant.fields.len() != 1 {
+        return Err((
+            variant.span,
+            "`#[from]` can only be used on a variant with exactly one field",
+        ));
+    }
+    let construct = match variant.style {
+        Style::Named => format!("{} {{ {}: source }}", variant.path(), field.binding),
+        _ => format!("{}(source)", variant.path()),
+    };
+    Ok(format!(
+        "impl ::core::convert::From<{ty}> for {name} {{
+            fn from(source: {ty}) -> Self {{
+                {construct}
+            }}
+        }}",
+        ty = field.ty,
+        name = name,
+        construct = construct,
+    ))
+}
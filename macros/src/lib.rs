@@ -0,0 +1,153 @@
+//! Procedural macros for the [ees](https://docs.rs/ees) error-handling library.
+//!
+//! These are re-exported by `ees` behind the `macros` and `derive` features,
+//! and should be used through that crate rather than depending on this one
+//! directly.
+
+mod derive;
+
+use proc_macro::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+
+/// Wrap an ordinary `fn main() -> ees::Result<()>`, printing the error chain
+/// and selecting the exit code if it fails
+///
+/// The return type can be any `Result<T, E>` where `T` implements
+/// [Termination](std::process::Termination), and `E` converts into
+/// `ees::MainError`. Returning a `MainError` directly lets the function choose
+/// its exit code with `MainError::with_exit_code`.
+///
+/// `async fn main()` is supported by naming a runtime, either
+/// `#[ees::main(tokio)]` or `#[ees::main(async_std)]`. The runtime crate needs
+/// to be a dependency of the calling crate.
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    match expand_main(args, item) {
+        Ok(tokens) => tokens,
+        Err((span, message)) => compile_error(span, message),
+    }
+}
+
+/// Derive `Display` and `Error` for a struct or enum
+///
+/// The message of each struct or enum variant is given with
+/// `#[error("...")]`, where fields can be referred to by name (`{path}`) or by
+/// index (`{0}`). Additional format arguments are also supported, e.g.
+/// `#[error("{}", path.display())]`. `#[error(transparent)]` forwards both the
+/// message and the source to the only field.
+///
+/// The field marked with `#[source]`, or otherwise the field named `source`,
+/// is returned by `Error::source`. This can be any error type, an `ees::Error`,
+/// or an `Option` of a concrete error type. `#[from]` additionally generates a `From` impl,
+/// and requires the variant to have exactly one field.
+///
+/// Generic types are not supported.
+#[proc_macro_derive(Derive, attributes(error, source, from))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    match derive::expand(input) {
+        Ok(tokens) => tokens,
+        Err((span, message)) => compile_error(span, message),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Runtime {
+    Tokio,
+    AsyncStd,
+}
+
+type Error = (Span, &'static str);
+
+fn expand_main(args: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+    let runtime = parse_runtime(args)?;
+    let mut item: Vec<TokenTree> = item.into_iter().collect();
+
+    let fn_index = item.iter().position(|token| is_ident(token, "fn")).ok_or((
+        Span::call_site(),
+        "`#[ees::main]` can only be applied to a function",
+    ))?;
+    let is_async = item[..fn_index]
+        .iter()
+        .any(|token| is_ident(token, "async"));
+    let name_span = match item.get(fn_index + 1) {
+        Some(TokenTree::Ident(name)) => name.span(),
+        _ => return Err((Span::call_site(), "expected a function name")),
+    };
+    item[fn_index + 1] = TokenTree::Ident(Ident::new("__ees_main", name_span));
+
+    let call = match (is_async, runtime) {
+        (false, None) => "::ees::internal::report_main(__ees_main())",
+        (true, Some(Runtime::Tokio)) => {
+            "match ::tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+                ::std::result::Result::Ok(runtime) => {
+                    ::ees::internal::report_main(runtime.block_on(__ees_main()))
+                }
+                ::std::result::Result::Err(error) => {
+                    ::ees::internal::report_main::<(), _>(::std::result::Result::Err(error))
+                }
+            }"
+        }
+        (true, Some(Runtime::AsyncStd)) => {
+            "::ees::internal::report_main(::async_std::task::block_on(__ees_main()))"
+        }
+        (true, None) => {
+            return Err((
+                name_span,
+                "`async fn main` requires a runtime, e.g. `#[ees::main(tokio)]`",
+            ))
+        }
+        (false, Some(_)) => {
+            return Err((
+                name_span,
+                "a runtime can only be specified for `async fn main`",
+            ))
+        }
+    };
+
+    let mut body: TokenStream = item.into_iter().collect();
+    body.extend(parse(call));
+    let mut output = parse("fn main() -> ::std::process::ExitCode");
+    output.extend(Some(TokenTree::Group(Group::new(Delimiter::Brace, body))));
+    Ok(output)
+}
+
+fn parse_runtime(args: TokenStream) -> Result<Option<Runtime>, Error> {
+    let mut args = args.into_iter();
+    let runtime = match args.next() {
+        None => return Ok(None),
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "tokio" => Runtime::Tokio,
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "async_std" => Runtime::AsyncStd,
+        Some(token) => {
+            return Err((
+                token.span(),
+                "unsupported runtime, expected `tokio` or `async_std`",
+            ))
+        }
+    };
+    if let Some(token) = args.next() {
+        return Err((token.span(), "unexpected token"));
+    }
+    Ok(Some(runtime))
+}
+
+fn is_ident(token: &TokenTree, name: &str) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident.to_string() == name)
+}
+
+fn is_punct(token: &TokenTree, c: char) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == c)
+}
+
+fn parse(source: &str) -> TokenStream {
+    source.parse().expect("generated code should be valid")
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let tokens = parse(&format!("::std::compile_error!({:?});", message));
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+use std::io;
+
+#[derive(Debug, ees::Derive)]
+enum ConfigError {
+    #[error("failed to read {path}")]
+    Read {
+        path: String,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("invalid port {0} (expected at most {max})", max = u16::MAX)]
+    InvalidPort(u32),
+    #[error("missing {0}")]
+    Missing(&'static str, Option<io::Error>),
+    #[error("io error")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Other(#[from] ees::Error),
+}
+
+#[derive(Debug, ees::Derive)]
+#[error("request failed with status {status}")]
+pub struct RequestError {
+    pub status: u16,
+    source: Option<io::Error>,
+}
+
+#[derive(Debug, ees::Derive)]
+#[error("unit error")]
+struct UnitError;
+
+#[derive(Debug, ees::Derive)]
+enum Never {}
+
+#[derive(Debug, ees::Derive)]
+enum FlagError {
+    #[error("bad flag {f}")]
+    Flag { f: String },
+}
+
+#[test]
+fn display_and_source() {
+    let e = ConfigError::Read {
+        path: "config.toml".to_string(),
+        cause: io::Error::new(io::ErrorKind::NotFound, "not found"),
+    };
+    let e = ees::wrap!(e, "failed to load config");
+    assert_eq!(
+        ees::print_error_chain(e).to_string(),
+        "failed to load config: failed to read config.toml: not found"
+    );
+
+    let e = ConfigError::InvalidPort(70000);
+    assert_eq!(e.to_string(), "invalid port 70000 (expected at most 65535)");
+    assert!(std::error::Error::source(&e).is_none());
+
+    let e = ConfigError::Missing("host", None);
+    assert_eq!(ees::print_error_chain(e).to_string(), "missing host");
+}
+
+#[test]
+fn from_and_transparent() {
+    let e: ConfigError = io::Error::other("disk full").into();
+    assert_eq!(ees::print_error_chain(e).to_string(), "io error: disk full");
+
+    let e: ConfigError = ees::Error::from(ees::wrap!(ees::err!("root"), "outer")).into();
+    assert_eq!(e.to_string(), "outer");
+    assert_eq!(ees::print_error_chain(e).to_string(), "outer: root");
+}
+
+#[test]
+fn structs() {
+    let e = RequestError {
+        status: 404,
+        source: Some(io::Error::other("connection reset")),
+    };
+    assert_eq!(
+        ees::print_error_chain(e).to_string(),
+        "request failed with status 404: connection reset"
+    );
+    assert_eq!(UnitError.to_string(), "unit error");
+    let _ = |never: Never| never.to_string();
+}
+
+#[test]
+fn field_named_like_the_formatter() {
+    let e = FlagError::Flag {
+        f: "-x".to_string(),
+    };
+    assert_eq!(e.to_string(), "bad flag -x");
+}
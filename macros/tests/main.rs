@@ -0,0 +1,42 @@
+use std::process::ExitCode;
+
+mod success {
+    #[ees::main]
+    fn main() -> ees::Result<()> {
+        Ok(())
+    }
+
+    pub fn run() -> std::process::ExitCode {
+        main()
+    }
+}
+
+mod failure {
+    #[ees::main]
+    fn main() -> ees::Result<()> {
+        ees::bail!("failed to do work");
+    }
+
+    pub fn run() -> std::process::ExitCode {
+        main()
+    }
+}
+
+mod custom_exit_code {
+    #[ees::main]
+    fn main() -> Result<(), ees::MainError> {
+        let error = ees::MainError::from(ees::err!("invalid usage"));
+        Err(error.with_exit_code(2))
+    }
+
+    pub fn run() -> std::process::ExitCode {
+        main()
+    }
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(success::run(), ExitCode::SUCCESS);
+    assert_eq!(failure::run(), ExitCode::FAILURE);
+    assert_eq!(custom_exit_code::run(), ExitCode::from(2));
+}
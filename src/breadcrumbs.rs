@@ -0,0 +1,88 @@
+use std::{cell::RefCell, collections::VecDeque, fmt};
+
+/// The number of breadcrumbs that are kept on each thread
+const CAPACITY: usize = 16;
+
+thread_local! {
+    static BREADCRUMBS: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Record a breadcrumb on the current thread, see [breadcrumb!](crate::breadcrumb)
+pub(crate) fn record(args: fmt::Arguments<'_>) {
+    let message = match args.as_str() {
+        Some(message) => message.to_string(),
+        None => args.to_string(),
+    };
+    BREADCRUMBS.with(|breadcrumbs| {
+        // a breadcrumb recorded while formatting another one is dropped
+        if let Ok(mut breadcrumbs) = breadcrumbs.try_borrow_mut() {
+            if breadcrumbs.len() == CAPACITY {
+                breadcrumbs.pop_front();
+            }
+            breadcrumbs.push_back(message);
+        }
+    });
+}
+
+/// The most recent breadcrumbs recorded on the current thread with
+/// [breadcrumb!](crate::breadcrumb), oldest first
+///
+/// This is useful for attaching them to errors that are sent elsewhere, e.g.
+/// by a [Reporter](crate::Reporter).
+///
+/// ```
+/// ees::breadcrumb!("loaded {} plugins", 3);
+/// assert_eq!(ees::breadcrumbs(), ["loaded 3 plugins"]);
+/// ```
+#[must_use]
+pub fn breadcrumbs() -> Vec<String> {
+    BREADCRUMBS.with(|breadcrumbs| match breadcrumbs.try_borrow() {
+        Ok(breadcrumbs) => breadcrumbs.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    })
+}
+
+/// The breadcrumbs of the current thread, written as a "Recent activity:"
+/// section after an empty line, or nothing if there are none
+pub(crate) struct Breadcrumbs(Vec<String>);
+
+impl Breadcrumbs {
+    pub(crate) fn capture() -> Self {
+        Self(breadcrumbs())
+    }
+}
+
+impl fmt::Display for Breadcrumbs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        write!(f, "\n\nRecent activity:")?;
+        for breadcrumb in &self.0 {
+            write!(f, "\n    {}", breadcrumb)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn breadcrumbs() {
+        assert_eq!(super::Breadcrumbs::capture().to_string(), "");
+        for i in 0..20 {
+            crate::breadcrumb!("step {}", i);
+        }
+        let breadcrumbs = super::breadcrumbs();
+        assert_eq!(breadcrumbs.len(), super::CAPACITY);
+        assert_eq!(breadcrumbs[0], "step 4");
+        assert_eq!(breadcrumbs[15], "step 19");
+
+        let e = crate::MainError::from(crate::err!("failed"));
+        assert!(e
+            .full_report()
+            .contains("failed\n\nRecent activity:\n    step 4\n    step 5\n"));
+        assert!(format!("{:?}", e).contains("failed\n\nRecent activity:\n    step 4\n"));
+        assert!(!format!("{:#?}", e).contains("Recent activity:"));
+    }
+}
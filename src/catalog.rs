@@ -0,0 +1,188 @@
+use std::{fmt, fmt::Write as _, sync::RwLock};
+
+/// An error code declared in a catalog, with a message template and an
+/// optional documentation URL
+///
+/// Catalogs are registered with [register_catalog], and their errors are
+/// created with [coded_err!](crate::coded_err). Templates can refer to the
+/// arguments of `coded_err!` by name, e.g. `{path}`, and `{{` and `}}` are
+/// written as literal braces.
+///
+/// ```
+/// static CATALOG: &[ees::CatalogEntry] = &[
+///     ees::CatalogEntry::new("E0042", "missing field `{field}` in {path}")
+///         .url("https://example.com/errors/E0042"),
+///     ees::CatalogEntry::new("E0043", "empty configuration file"),
+/// ];
+///
+/// ees::register_catalog(CATALOG);
+/// let e = ees::coded_err!(E0042, field = "name", path = "app.toml");
+/// assert_eq!(e.to_string(), "missing field `name` in app.toml");
+/// assert_eq!(ees::error_code(&e), Some(ees::ErrorCode::Name("E0042")));
+/// ```
+///
+/// A [MainError](crate::MainError) whose code has a URL ends with a line
+/// pointing to the documentation:
+///
+/// ```
+/// # static CATALOG: &[ees::CatalogEntry] = &[
+/// #     ees::CatalogEntry::new("E0042", "missing field `{field}` in {path}")
+/// #         .url("https://example.com/errors/E0042"),
+/// # ];
+/// # ees::register_catalog(CATALOG);
+/// let e = ees::MainError::from(ees::coded_err!(E0042, field = "name", path = "app.toml"));
+/// # #[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
+/// assert_eq!(
+///     format!("{:?}", e),
+///     "missing field `name` in app.toml\n\n\
+///      For more information about this error, see https://example.com/errors/E0042"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogEntry {
+    code: &'static str,
+    message: &'static str,
+    url: Option<&'static str>,
+}
+
+impl CatalogEntry {
+    /// Declare an error code with its message template
+    #[must_use]
+    pub const fn new(code: &'static str, message: &'static str) -> Self {
+        Self {
+            code,
+            message,
+            url: None,
+        }
+    }
+
+    /// Set the URL of the documentation of this error
+    #[must_use]
+    pub const fn url(mut self, url: &'static str) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    /// The error code, e.g. `"E0042"`
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// The message template
+    #[must_use]
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// The URL of the documentation of this error, if any
+    #[must_use]
+    pub fn documentation(&self) -> Option<&'static str> {
+        self.url
+    }
+}
+
+static CATALOGS: RwLock<Vec<&'static [CatalogEntry]>> = RwLock::new(Vec::new());
+
+/// Register the error codes of a catalog, in addition to previously registered
+/// catalogs
+///
+/// If a code is declared more than once, the first registered entry is used.
+pub fn register_catalog(catalog: &'static [CatalogEntry]) {
+    // the catalogs are only ever appended to, so a poisoned lock holds valid
+    // data
+    let mut catalogs = CATALOGS.write().unwrap_or_else(|e| e.into_inner());
+    catalogs.push(catalog);
+}
+
+/// Look up a registered error code
+#[must_use]
+pub fn catalog_entry(code: &str) -> Option<&'static CatalogEntry> {
+    let catalogs = CATALOGS.read().unwrap_or_else(|e| e.into_inner());
+    catalogs
+        .iter()
+        .flat_map(|catalog| catalog.iter())
+        .find(|entry| entry.code == code)
+}
+
+/// The message of an error created with `coded_err!`
+///
+/// Codes that haven't been registered are written with their arguments, e.g.
+/// `E0042 (field = name)`.
+pub(crate) fn format_message(code: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut message = String::new();
+    let template = match catalog_entry(code) {
+        Some(entry) => entry.message,
+        None => {
+            message.push_str(code);
+            for (i, (name, value)) in args.iter().enumerate() {
+                let separator = if i == 0 { " (" } else { ", " };
+                let _ = write!(message, "{}{} = {}", separator, name, value);
+            }
+            if !args.is_empty() {
+                message.push(')');
+            }
+            return message;
+        }
+    };
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        message.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            message.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            message.push('}');
+            rest = after;
+        } else if let Some((name, after)) =
+            rest.strip_prefix('{').and_then(|rest| rest.split_once('}'))
+        {
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => {
+                    let _ = write!(message, "{}", value);
+                }
+                // unknown placeholders are kept, so that the mistake is visible
+                None => {
+                    let _ = write!(message, "{{{}}}", name);
+                }
+            }
+            rest = after;
+        } else {
+            message.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    static CATALOG: &[super::CatalogEntry] = &[
+        super::CatalogEntry::new("T0001", "{{literal}} {a} and {b}, {missing} }"),
+        super::CatalogEntry::new("T0002", "no placeholders").url("https://example.com"),
+    ];
+
+    #[test]
+    fn catalog() {
+        super::register_catalog(CATALOG);
+        assert_eq!(
+            super::format_message("T0001", &[("a", &1), ("b", &"two")]),
+            "{literal} 1 and two, {missing} }"
+        );
+        assert_eq!(
+            super::catalog_entry("T0002").unwrap().documentation(),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            super::format_message("T9999", &[("a", &1), ("b", &2)]),
+            "T9999 (a = 1, b = 2)"
+        );
+        assert_eq!(super::format_message("T9999", &[]), "T9999");
+
+        let (e, line) = (crate::coded_err!(T0002), line!());
+        assert_eq!(e.to_string(), "no placeholders");
+        assert_eq!(crate::location(&e).unwrap().line(), line);
+    }
+}
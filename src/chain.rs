@@ -0,0 +1,1468 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::{any, error, fmt, fmt::Write as _, iter};
+
+/// The complete error chain of an error, as returned by
+/// [print_error_chain](crate::print_error_chain)
+///
+/// Displaying it prints every error in the chain, and the errors can also be
+/// inspected individually. An error chain that contains a cycle is cut short
+/// before it repeats itself, and printed with a `... (cycle detected)` marker.
+///
+/// Errors with an empty message, e.g. from wrappers that only add a type, are
+/// skipped when printing the chain, but are still returned by
+/// [iter](Self::iter).
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorChain<E> {
+    error: E,
+}
+
+impl<E: error::Error> ErrorChain<E> {
+    pub(crate) fn new(error: E) -> Self {
+        Self { error }
+    }
+
+    /// Iterate over the errors in the chain, starting with the outermost error
+    /// and ending with the root cause
+    ///
+    /// If the chain contains a cycle, each error is only returned once.
+    pub fn iter(&self) -> Chain<'_> {
+        Chain::new(&self.error)
+    }
+
+    /// The number of errors in the chain, which is always at least one
+    #[must_use]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// The innermost error, i.e. the root cause
+    #[must_use]
+    pub fn root(&self) -> &(dyn error::Error + '_) {
+        self.iter().last().expect("an error chain is never empty")
+    }
+
+    /// The causes of the outermost error, separated with colons, or an empty
+    /// string if there are none
+    ///
+    /// Together with [root](Self::root), this is useful for recording an
+    /// error as structured fields, e.g. with `tracing`:
+    ///
+    /// ```ignore
+    /// let chain = ees::print_error_chain(error.as_ref());
+    /// tracing::error!(
+    ///     error = %error,
+    ///     error.cause_chain = %chain.causes(),
+    ///     error.root_cause = %chain.root(),
+    ///     "request failed",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn causes(&self) -> impl fmt::Display + '_ {
+        Causes(&self.error)
+    }
+}
+
+struct Causes<'a>(&'a dyn error::Error);
+
+impl fmt::Display for Causes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chain = Chain::new(self.0).skip_empty();
+        chain.next();
+        let mut separator = "";
+        let flags = Flags {
+            alternate: false,
+            plus: false,
+            type_names: None,
+        };
+        for error in &mut chain {
+            write!(f, "{}{}", separator, Layer::new(error, flags))?;
+            separator = ": ";
+        }
+        if chain.cycle_detected() {
+            write!(f, "{}{}", separator, CYCLE_DETECTED)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, E: error::Error> IntoIterator for &'a ErrorChain<E> {
+    type Item = &'a (dyn error::Error + 'a);
+    type IntoIter = Chain<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the errors in an [ErrorChain]
+#[derive(Debug, Clone)]
+pub struct Chain<'a> {
+    next: Option<&'a (dyn error::Error + 'a)>,
+    // the number of errors left before the chain starts repeating itself
+    remaining: Option<usize>,
+    // the number of errors left before the chain is truncated
+    limit: Option<usize>,
+    skip_empty: bool,
+}
+
+impl<'a> Chain<'a> {
+    pub(crate) fn new(error: &'a (dyn error::Error + 'a)) -> Self {
+        Self {
+            next: Some(error),
+            remaining: distinct_errors(error),
+            limit: None,
+            skip_empty: false,
+        }
+    }
+
+    /// Skip errors with an empty message, which would otherwise be printed
+    /// as e.g. `outer: : root`
+    pub(crate) fn skip_empty(mut self) -> Self {
+        self.skip_empty = true;
+        self
+    }
+
+    /// Stop after at most `limit` errors
+    fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Whether the remaining errors have been skipped because the chain
+    /// contains a cycle
+    pub(crate) fn cycle_detected(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// The number of errors that have been skipped because of the limit
+    fn truncated(&self) -> usize {
+        match self.limit {
+            Some(0) => self.clone().limit(None).count(),
+            _ => 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn error::Error + 'a);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(limit) = &mut self.limit {
+            *limit = limit.checked_sub(1)?;
+        }
+        loop {
+            if let Some(remaining) = &mut self.remaining {
+                *remaining = remaining.checked_sub(1)?;
+            }
+            let error = self.next?;
+            self.next = source(error);
+            if !self.skip_empty || has_message(error) {
+                return Some(error);
+            }
+        }
+    }
+}
+
+fn has_message(error: &dyn error::Error) -> bool {
+    struct Probe(bool);
+
+    impl fmt::Write for Probe {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            if s.is_empty() {
+                return Ok(());
+            }
+            self.0 = true;
+            // the rest of the message isn't needed
+            Err(fmt::Error)
+        }
+    }
+
+    let mut probe = Probe(false);
+    let _ = write!(probe, "{}", error);
+    probe.0
+}
+
+/// Like [Chain], but keeping the `'static` bound that is needed for downcasting
+pub(crate) fn static_chain<'a>(
+    error: &'a (dyn error::Error + 'static),
+) -> impl Iterator<Item = &'a (dyn error::Error + 'static)> {
+    let len = Chain::new(error).count();
+    iter::successors(Some(error), |error| error.source()).take(len)
+}
+
+fn source<'a>(error: &'a (dyn error::Error + 'a)) -> Option<&'a (dyn error::Error + 'a)> {
+    error.source().map(|source| source as _)
+}
+
+fn same(a: &dyn error::Error, b: &dyn error::Error) -> bool {
+    // vtables aren't guaranteed to be unique, so only the addresses are compared
+    core::ptr::addr_eq(a, b)
+}
+
+/// Returns the number of distinct errors in the chain if it contains a cycle,
+/// using Floyd's algorithm so that no allocation is needed
+fn distinct_errors(head: &dyn error::Error) -> Option<usize> {
+    let (mut slow, mut fast) = (head, head);
+    loop {
+        fast = source(source(fast)?)?;
+        slow = source(slow)?;
+        if same(slow, fast) {
+            break;
+        }
+    }
+    // the cycle starts after `start` errors, and contains `length` errors
+    let mut start = 0;
+    slow = head;
+    while !same(slow, fast) {
+        slow = source(slow)?;
+        fast = source(fast)?;
+        start += 1;
+    }
+    let mut length = 1;
+    fast = source(slow)?;
+    while !same(slow, fast) {
+        fast = source(fast)?;
+        length += 1;
+    }
+    Some(start + length)
+}
+
+/// Appended to a chain that has been cut short because it contains a cycle
+const CYCLE_DETECTED: &str = "... (cycle detected)";
+
+impl iter::FusedIterator for Chain<'_> {}
+
+impl<E: error::Error> fmt::Display for ErrorChain<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = ChainFormat::new().caused_by(f.alternate());
+        format.write(f, &self.error, Flags::of(f))
+    }
+}
+
+/// Escapes line breaks and removes other control characters
+struct SingleLine<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for SingleLine<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for part in s.split_inclusive(char::is_control) {
+            let mut chars = part.chars();
+            match chars.next_back() {
+                Some('\n') => write!(self.0, "{}\\n", chars.as_str())?,
+                Some('\r') => write!(self.0, "{}\\r", chars.as_str())?,
+                Some(c) if c.is_control() => self.0.write_str(chars.as_str())?,
+                _ => self.0.write_str(part)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A builder for customizing how an error chain is printed
+///
+/// The default format matches the `{}` format of [ErrorChain], and
+/// `.caused_by(true)` matches its `{:#}` format.
+///
+/// ```
+/// use ees::ChainFormat;
+///
+/// let e = ees::wrap!(ees::err!("timed out"), "connection lost");
+/// let format = ChainFormat::new().separator(" -> ");
+/// assert_eq!(format.display(&e).to_string(), "connection lost -> timed out");
+///
+/// let format = ChainFormat::new().caused_by(true).indent(2);
+/// assert_eq!(
+///     format.display(&e).to_string(),
+///     "connection lost\n\nCaused by:\n  timed out"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainFormat {
+    separator: &'static str,
+    layout: Layout,
+    indent: usize,
+    max_causes: Option<usize>,
+    margin: usize,
+    reverse: bool,
+    first_number: usize,
+    number_width: usize,
+    number_alignment: fmt::Alignment,
+    type_names: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    Inline,
+    CausedBy,
+    Tree,
+    SingleLine,
+}
+
+impl Default for ChainFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChainFormat {
+    /// A format that prints all errors on a single line, separated with colons
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            separator: ": ",
+            layout: Layout::Inline,
+            indent: 4,
+            max_causes: None,
+            margin: 0,
+            reverse: false,
+            first_number: 0,
+            number_width: 1,
+            number_alignment: fmt::Alignment::Right,
+            type_names: false,
+        }
+    }
+
+    /// Set the separator between errors, e.g. `" -> "` or `"\n"` (the default
+    /// is `": "`)
+    ///
+    /// This has no effect if the "Caused by:" section or the tree layout is
+    /// enabled. The separator is written as is, even in the single-line
+    /// layout.
+    #[must_use]
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Print the causes in a "Caused by:" section after the outermost error,
+    /// each on its own line (the default is `false`)
+    ///
+    /// If there is more than one cause, they are numbered. Consecutive causes
+    /// with identical messages are printed once, followed by e.g.
+    /// `(repeated 3 times)`.
+    #[must_use]
+    pub const fn caused_by(mut self, caused_by: bool) -> Self {
+        self.layout = if caused_by {
+            Layout::CausedBy
+        } else {
+            Layout::Inline
+        };
+        self
+    }
+
+    /// Print the causes as an indented tree below the outermost error, with
+    /// each cause nested under the previous one (the default is `false`)
+    ///
+    /// ```text
+    /// failed to load config
+    ///   └─ caused by: failed to read config.toml
+    ///      └─ caused by: permission denied
+    /// ```
+    ///
+    /// This can't be combined with the "Caused by:" section, so whichever of the
+    /// two is enabled last takes effect.
+    #[must_use]
+    pub const fn tree(mut self, tree: bool) -> Self {
+        self.layout = if tree { Layout::Tree } else { Layout::Inline };
+        self
+    }
+
+    /// Like the default layout, but guarantee that the output is a single
+    /// line, e.g. for line-oriented log shippers (the default is `false`)
+    ///
+    /// Line breaks in messages are escaped as `\n` and `\r`, and other
+    /// control characters are removed.
+    ///
+    /// ```
+    /// let e = ees::wrap!(ees::err!("line 1\nline 2\x07"), "failed");
+    /// let format = ees::ChainFormat::new().single_line(true).separator(" | ");
+    /// assert_eq!(format.display(&e).to_string(), "failed | line 1\\nline 2");
+    /// ```
+    ///
+    /// Like the tree layout, this can't be combined with the "Caused by:"
+    /// section.
+    #[must_use]
+    pub const fn single_line(mut self, single_line: bool) -> Self {
+        self.layout = if single_line {
+            Layout::SingleLine
+        } else {
+            Layout::Inline
+        };
+        self
+    }
+
+    /// Set the number of spaces that causes are indented with in the
+    /// "Caused by:" section (the default is 4)
+    #[must_use]
+    pub const fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Print at most `max_causes` causes after the outermost error, followed by
+    /// e.g. `... and 3 more` if there are more (the default is no limit)
+    ///
+    /// ```
+    /// let e = ees::wrap!(ees::wrap!(ees::err!("root"), "middle"), "outer");
+    /// let format = ees::ChainFormat::new().max_causes(1);
+    /// assert_eq!(format.display(&e).to_string(), "outer: middle: ... and 1 more");
+    /// ```
+    #[must_use]
+    pub const fn max_causes(mut self, max_causes: usize) -> Self {
+        self.max_causes = Some(max_causes);
+        self
+    }
+
+    /// Print the root cause first, followed by the errors that wrap it (the
+    /// default is `false`)
+    ///
+    /// With the "Caused by:" section, the root cause is printed on the first
+    /// line, and the other errors are listed in a "Context:" section. The
+    /// tree layout ignores this option. [max_causes](Self::max_causes) keeps
+    /// the innermost errors, and omits the outermost ones.
+    ///
+    /// ```
+    /// let e = ees::wrap!(ees::err!("permission denied"), "failed to read config.toml");
+    /// let e = ees::wrap!(e, "failed to start");
+    /// let format = ees::ChainFormat::new().reverse(true);
+    /// assert_eq!(
+    ///     format.display(&e).to_string(),
+    ///     "permission denied: failed to read config.toml: failed to start"
+    /// );
+    /// assert_eq!(
+    ///     format.caused_by(true).display(&e).to_string(),
+    ///     "permission denied\n\n\
+    ///      Context:\n    \
+    ///      0: failed to read config.toml\n    \
+    ///      1: failed to start"
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Shift every line of the output right by `margin` spaces, e.g. to embed
+    /// the report in a larger block of output (the default is 0)
+    ///
+    /// Empty lines are left empty.
+    ///
+    /// ```
+    /// let e = ees::wrap!(ees::err!("timed out"), "connection lost");
+    /// let format = ees::ChainFormat::new().caused_by(true).margin(2);
+    /// assert_eq!(
+    ///     format.display(&e).to_string(),
+    ///     "  connection lost\n\n  Caused by:\n      timed out"
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Set the number of the first cause in the "Caused by:" and "Context:"
+    /// sections (the default is 0)
+    ///
+    /// ```
+    /// let e = ees::wrap!(ees::wrap!(ees::err!("root"), "middle"), "outer");
+    /// let format = ees::ChainFormat::new().caused_by(true).first_number(1);
+    /// assert_eq!(
+    ///     format.display(&e).to_string(),
+    ///     "outer\n\nCaused by:\n    1: middle\n    2: root"
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn first_number(mut self, first_number: usize) -> Self {
+        self.first_number = first_number;
+        self
+    }
+
+    /// Set the width of the column that the numbers of the causes are printed
+    /// in, after the indentation (the default is 1)
+    ///
+    /// Numbers that don't fit into the column are printed in full. With the
+    /// default right alignment, they extend into the indentation.
+    #[must_use]
+    pub const fn number_width(mut self, number_width: usize) -> Self {
+        self.number_width = number_width;
+        self
+    }
+
+    /// Set the alignment of the numbers of the causes within their column
+    /// (the default is right-aligned)
+    ///
+    /// ```
+    /// use std::fmt::Alignment;
+    ///
+    /// let e = ees::wrap!(ees::wrap!(ees::err!("root"), "middle"), "outer");
+    /// let format = ees::ChainFormat::new()
+    ///     .caused_by(true)
+    ///     .indent(2)
+    ///     .number_width(3)
+    ///     .number_alignment(Alignment::Left);
+    /// assert_eq!(
+    ///     format.display(&e).to_string(),
+    ///     "outer\n\nCaused by:\n  0  : middle\n  1  : root"
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn number_alignment(mut self, number_alignment: fmt::Alignment) -> Self {
+        self.number_alignment = number_alignment;
+        self
+    }
+
+    /// Append the type of each error after its message, e.g. to find out which
+    /// crate an error comes from (the default is `false`)
+    ///
+    /// ```
+    /// let e = std::fs::File::open("does-not-exist.toml").unwrap_err();
+    /// let e = ees::wrap!(e, "failed to open config");
+    /// let format = ees::ChainFormat::new().caused_by(true).type_names(true);
+    /// let report = format.display(&e).to_string();
+    /// assert!(report.starts_with("failed to open config\n\nCaused by:\n    "));
+    /// assert!(report.ends_with(" (std::io::Error)"));
+    /// ```
+    ///
+    /// The types are only known for the outermost error, and for errors that
+    /// have been wrapped with [wrap!](crate::wrap) or e.g.
+    /// [ResultExt::wrap_err](crate::ResultExt::wrap_err). Errors created by
+    /// this crate have no type name. Private `error` modules are omitted from
+    /// the names.
+    #[must_use]
+    pub const fn type_names(mut self, type_names: bool) -> Self {
+        self.type_names = type_names;
+        self
+    }
+
+    /// Print the complete error chain of an error using this format
+    ///
+    /// As with [print_error_chain](crate::print_error_chain), `{:+}` appends
+    /// source locations and `{:#}` includes fields.
+    #[must_use]
+    pub fn display<E: error::Error>(self, error: E) -> FormattedChain<E> {
+        FormattedChain {
+            format: self,
+            error,
+        }
+    }
+
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        error: &dyn error::Error,
+        flags: Flags<'_>,
+    ) -> fmt::Result {
+        let limit = self
+            .max_causes
+            .map(|max_causes| max_causes.saturating_add(1));
+        if self.reverse && self.layout != Layout::Tree {
+            return self.write_reversed(f, error, flags, limit);
+        }
+        let mut chain = Chain::new(error).skip_empty().limit(limit);
+        if self.layout == Layout::SingleLine {
+            let mut separator = "";
+            for error in &mut chain {
+                f.write_str(separator)?;
+                write!(SingleLine(f), "{}", Layer::new(error, flags))?;
+                separator = self.separator;
+            }
+            if chain.cycle_detected() {
+                write!(f, "{}{}", self.separator, CYCLE_DETECTED)?;
+            }
+            if chain.truncated() > 0 {
+                write!(f, "{}{}", self.separator, Truncated(chain.truncated()))?;
+            }
+            return Ok(());
+        }
+        if let Some(error) = chain.next() {
+            write!(f, "{}", Layer::new(error, flags))?;
+        }
+        match self.layout {
+            Layout::Inline => {
+                for error in &mut chain {
+                    write!(f, "{}{}", self.separator, Layer::new(error, flags))?;
+                }
+                if chain.cycle_detected() {
+                    write!(f, "{}{}", self.separator, CYCLE_DETECTED)?;
+                }
+                if chain.truncated() > 0 {
+                    write!(f, "{}{}", self.separator, Truncated(chain.truncated()))?;
+                }
+            }
+            Layout::CausedBy => write_causes(f, chain, flags, self)?,
+            Layout::Tree => {
+                // each branch starts below the message of its parent
+                let mut indent = 2;
+                for error in &mut chain {
+                    // continuation lines line up with the start of the message
+                    let layer = Layer::new(error, flags).indent(indent + 14);
+                    write!(f, "\n{:indent$}└─ caused by: {}", "", layer)?;
+                    indent += 3;
+                }
+                if chain.cycle_detected() {
+                    write!(f, "\n{:indent$}{}", "", CYCLE_DETECTED)?;
+                }
+                if chain.truncated() > 0 {
+                    write!(f, "\n{:indent$}{}", "", Truncated(chain.truncated()))?;
+                }
+            }
+            Layout::SingleLine => unreachable!("handled above"),
+        }
+        if self.layout != Layout::Inline {
+            crate::report::write_sections(f, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl ChainFormat {
+    fn write_reversed(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        error: &dyn error::Error,
+        flags: Flags<'_>,
+        limit: Option<usize>,
+    ) -> fmt::Result {
+        let mut chain = Chain::new(error).skip_empty();
+        let mut errors: Vec<_> = (&mut chain).collect();
+        errors.reverse();
+        let truncated = errors.len().saturating_sub(limit.unwrap_or(usize::MAX));
+        errors.truncate(errors.len() - truncated);
+        // the markers are written where the omitted errors would be
+        let cycle = Some(CYCLE_DETECTED).filter(|_| chain.cycle_detected());
+        let truncated = Some(Truncated(truncated)).filter(|truncated| truncated.0 > 0);
+        if self.layout != Layout::CausedBy {
+            let mut separator = "";
+            if let Some(cycle) = cycle {
+                f.write_str(cycle)?;
+                separator = self.separator;
+            }
+            for error in errors {
+                f.write_str(separator)?;
+                if self.layout == Layout::SingleLine {
+                    write!(SingleLine(f), "{}", Layer::new(error, flags))?;
+                } else {
+                    write!(f, "{}", Layer::new(error, flags))?;
+                }
+                separator = self.separator;
+            }
+            if let Some(truncated) = truncated {
+                write!(f, "{}{}", separator, truncated)?;
+            }
+            return Ok(());
+        }
+
+        let (root, context) = errors.split_first().expect("an error chain is never empty");
+        write!(f, "{}", Layer::new(*root, flags))?;
+        if !context.is_empty() || cycle.is_some() || truncated.is_some() {
+            write!(f, "\n\nContext:")?;
+        }
+        let indent = self.indent;
+        if let Some(cycle) = cycle {
+            write!(f, "\n{:indent$}{}", "", cycle)?;
+        }
+        if context.len() > 1 {
+            for (n, error) in (self.first_number..).zip(context) {
+                self.write_numbered(f, n, Layer::new(*error, flags))?;
+            }
+            if let Some(truncated) = truncated {
+                write!(
+                    f,
+                    "\n{:width$}  {}",
+                    "",
+                    truncated,
+                    width = self.number_column()
+                )?;
+            }
+        } else {
+            for error in context {
+                let layer = Layer::new(*error, flags).indent(indent);
+                write!(f, "\n{:indent$}{}", "", layer)?;
+            }
+            if let Some(truncated) = truncated {
+                write!(f, "\n{:indent$}{}", "", truncated)?;
+            }
+        }
+        crate::report::write_sections(f, error)
+    }
+
+    /// The width of the indentation and the numbers of numbered causes
+    fn number_column(&self) -> usize {
+        self.indent + self.number_width
+    }
+
+    /// Write a numbered cause on a new line
+    fn write_numbered(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        n: usize,
+        layer: Layer<'_>,
+    ) -> fmt::Result {
+        let (indent, width) = (self.indent, self.number_width);
+        match self.number_alignment {
+            fmt::Alignment::Left => write!(f, "\n{:indent$}{:<width$}", "", n)?,
+            fmt::Alignment::Center => write!(f, "\n{:indent$}{:^width$}", "", n)?,
+            fmt::Alignment::Right => write!(f, "\n{:>column$}", n, column = indent + width)?,
+        }
+        // continuation lines line up with the start of the message
+        write!(f, ": {}", layer.indent(self.number_column() + 2))
+    }
+}
+
+/// An error chain printed with a custom [ChainFormat]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedChain<E> {
+    format: ChainFormat,
+    error: E,
+}
+
+impl<E: error::Error> fmt::Display for FormattedChain<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Flags::of(f);
+        if self.format.type_names {
+            flags.type_names = Some(TypeNames::new(&self.error));
+        }
+        if self.format.margin == 0 {
+            return self.format.write(f, &self.error, flags);
+        }
+
+        // the flags are captured before writing through the indenting writer,
+        // which uses a formatter without flags
+        struct Unindented<'a> {
+            format: ChainFormat,
+            error: &'a dyn error::Error,
+            flags: Flags<'a>,
+        }
+
+        impl fmt::Display for Unindented<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.format.write(f, self.error, self.flags)
+            }
+        }
+
+        let unindented = Unindented {
+            format: self.format,
+            error: &self.error,
+            flags,
+        };
+        let mut writer = IndentWriter {
+            f,
+            indent: self.format.margin,
+            newline: true,
+        };
+        write!(writer, "{}", unindented)
+    }
+}
+
+/// Write the "Caused by:" section of the alternate format, given the chain
+/// after the outermost error
+///
+/// Consecutive causes with identical messages are only written once.
+pub(crate) fn write_causes(
+    f: &mut fmt::Formatter<'_>,
+    causes: Chain<'_>,
+    flags: Flags<'_>,
+    format: &ChainFormat,
+) -> fmt::Result {
+    let indent = format.indent;
+    let numbered = Repeats::new(causes.clone(), flags).nth(1).is_some();
+    let mut causes = Repeats::new(causes, flags);
+    let (first_inner, count) = match causes.next() {
+        Some(cause) => cause,
+        // the outermost error is its own source
+        None if causes.chain.cycle_detected() => {
+            return write!(f, "\n\nCaused by:\n{:indent$}{}", "", CYCLE_DETECTED);
+        }
+        None if causes.chain.truncated() > 0 => {
+            let truncated = Truncated(causes.chain.truncated());
+            return write!(f, "\n\nCaused by:\n{:indent$}{}", "", truncated);
+        }
+        None => return Ok(()),
+    };
+    write!(f, "\n\nCaused by:")?;
+    if numbered {
+        // the numbers are padded, so that the messages line up
+        let width = format.number_column();
+        format.write_numbered(f, format.first_number, Layer::new(first_inner, flags))?;
+        write_repeated(f, count)?;
+        for (n, (error, count)) in (format.first_number + 1..).zip(&mut causes) {
+            format.write_numbered(f, n, Layer::new(error, flags))?;
+            write_repeated(f, count)?;
+        }
+        if causes.chain.cycle_detected() {
+            write!(f, "\n{:>width$}  {}", "", CYCLE_DETECTED)?;
+        }
+        if causes.chain.truncated() > 0 {
+            write!(
+                f,
+                "\n{:>width$}  {}",
+                "",
+                Truncated(causes.chain.truncated())
+            )?;
+        }
+    } else {
+        let layer = Layer::new(first_inner, flags).indent(indent);
+        write!(f, "\n{:indent$}{}", "", layer)?;
+        write_repeated(f, count)?;
+        if causes.chain.cycle_detected() {
+            write!(f, "\n{:indent$}{}", "", CYCLE_DETECTED)?;
+        }
+        if causes.chain.truncated() > 0 {
+            write!(f, "\n{:indent$}{}", "", Truncated(causes.chain.truncated()))?;
+        }
+    }
+    Ok(())
+}
+
+/// The error chain printed with the `Debug` representation of each error, as
+/// returned by [debug_error_chain](crate::debug_error_chain)
+pub(crate) struct DebugChain<E>(pub(crate) E);
+
+impl<E: error::Error> fmt::Display for DebugChain<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = f.alternate();
+        let write_debug = |f: &mut fmt::Formatter<'_>, error, indent| {
+            let mut writer = IndentWriter {
+                f,
+                indent,
+                newline: false,
+            };
+            if pretty {
+                write!(
+                    writer,
+                    "{}",
+                    crate::redact::Redacting(&format_args!("{:#?}", error))
+                )
+            } else {
+                write!(
+                    writer,
+                    "{}",
+                    crate::redact::Redacting(&format_args!("{:?}", error))
+                )
+            }
+        };
+        let mut chain = Chain::new(&self.0);
+        if let Some(error) = chain.next() {
+            write_debug(f, error, 0)?;
+        }
+        let numbered = chain.clone().nth(1).is_some();
+        let mut causes = chain.by_ref().peekable();
+        if causes.peek().is_some() {
+            f.write_str("\n\nCaused by:")?;
+        }
+        for (n, error) in causes.enumerate() {
+            if numbered {
+                write!(f, "\n{:>5}: ", n)?;
+                write_debug(f, error, 7)?;
+            } else {
+                f.write_str("\n    ")?;
+                write_debug(f, error, 4)?;
+            }
+        }
+        if chain.cycle_detected() {
+            write!(f, "\n    {}", CYCLE_DETECTED)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appended to a chain that has been cut short by [ChainFormat::max_causes]
+struct Truncated(usize);
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "... and {} more", self.0)
+    }
+}
+
+fn write_repeated(f: &mut fmt::Formatter<'_>, count: usize) -> fmt::Result {
+    if count > 1 {
+        write!(f, " (repeated {} times)", count)?;
+    }
+    Ok(())
+}
+
+/// Groups consecutive errors with identical messages, e.g. from retried
+/// wrapping, yielding the first error of each group and the group size
+struct Repeats<'a> {
+    chain: Chain<'a>,
+    flags: Flags<'a>,
+    peeked: Option<(&'a (dyn error::Error + 'a), u64)>,
+}
+
+impl<'a> Repeats<'a> {
+    fn new(chain: Chain<'a>, flags: Flags<'a>) -> Self {
+        Self {
+            chain,
+            flags,
+            peeked: None,
+        }
+    }
+
+    /// Messages are compared by hash, so that they don't need to be allocated
+    fn fingerprint(&self, error: &dyn error::Error) -> u64 {
+        // 64-bit FNV-1a
+        struct Hasher(u64);
+
+        impl fmt::Write for Hasher {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for byte in s.bytes() {
+                    self.0 ^= u64::from(byte);
+                    self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+                }
+                Ok(())
+            }
+        }
+
+        let mut hasher = Hasher(0xcbf2_9ce4_8422_2325);
+        // an error while formatting will also surface when writing the layer
+        let _ = write!(hasher, "{}", Layer::new(error, self.flags));
+        hasher.0
+    }
+}
+
+impl<'a> Iterator for Repeats<'a> {
+    type Item = (&'a (dyn error::Error + 'a), usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (error, fingerprint) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => {
+                let error = self.chain.next()?;
+                (error, self.fingerprint(error))
+            }
+        };
+        let mut count = 1;
+        while let Some(next) = self.chain.next() {
+            let next_fingerprint = self.fingerprint(next);
+            if next_fingerprint != fingerprint {
+                self.peeked = Some((next, next_fingerprint));
+                break;
+            }
+            count += 1;
+        }
+        Some((error, count))
+    }
+}
+
+/// Formatting flags that are forwarded to each error in the chain, so that
+/// errors created by this crate can append their fields (`{:#}`) and their
+/// location (`{:+}`)
+#[derive(Clone, Copy)]
+pub(crate) struct Flags<'a> {
+    pub(crate) alternate: bool,
+    pub(crate) plus: bool,
+    pub(crate) type_names: Option<TypeNames<'a>>,
+}
+
+impl Flags<'_> {
+    fn of(f: &fmt::Formatter<'_>) -> Self {
+        Self {
+            alternate: f.alternate(),
+            plus: f.sign_plus(),
+            type_names: None,
+        }
+    }
+}
+
+/// What is needed to find the type names of the errors in a chain, see
+/// [ChainFormat::type_names]
+#[derive(Clone, Copy)]
+pub(crate) struct TypeNames<'a> {
+    head: &'a (dyn error::Error + 'a),
+    outer: &'static str,
+}
+
+impl<'a> TypeNames<'a> {
+    pub(crate) fn new<E: error::Error>(head: &'a E) -> Self {
+        Self {
+            head,
+            outer: any::type_name::<E>(),
+        }
+    }
+
+    /// The type name of an error in the chain, if it is known
+    pub(crate) fn of(&self, error: &dyn error::Error) -> Option<String> {
+        if same(error, self.head) {
+            return specific_type_name(self.outer).map(short_type_name);
+        }
+        let mut parent = self.head;
+        for next in Chain::new(self.head).skip(1) {
+            if same(next, error) {
+                let name = crate::report::source_type(parent)?;
+                return specific_type_name(name).map(short_type_name);
+            }
+            parent = next;
+        }
+        None
+    }
+}
+
+/// Returns the name without references, or `None` for trait objects, boxes
+/// and the types of this crate, whose names don't add any information
+fn specific_type_name(name: &str) -> Option<&str> {
+    let name = name.trim_start_matches('&').trim_start_matches("mut ");
+    let crate_prefix = concat!(env!("CARGO_CRATE_NAME"), "::");
+    let unspecific = name.starts_with("dyn ")
+        || name.starts_with("alloc::boxed::Box<")
+        || name.starts_with(crate_prefix);
+    Some(name).filter(|_| !unspecific)
+}
+
+/// Omits private `error` modules from the paths in a type name, e.g.
+/// `std::io::error::Error` becomes `std::io::Error`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut rest = name;
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(rest.len());
+        let (path, after) = rest.split_at(end);
+        let segments: Vec<&str> = path.split("::").collect();
+        for (i, segment) in segments.iter().enumerate() {
+            let last = i + 1 == segments.len();
+            if !last && (*segment == "error" || *segment == "errors") {
+                continue;
+            }
+            short.push_str(segment);
+            if !last {
+                short.push_str("::");
+            }
+        }
+        let separator = after.chars().next().map_or(0, char::len_utf8);
+        short.push_str(&after[..separator]);
+        rest = &after[separator..];
+    }
+    short
+}
+
+/// A single error in the chain, translated by the [Localizer](crate::Localizer)
+/// if there is one
+pub(crate) struct Layer<'a> {
+    error: &'a (dyn error::Error + 'a),
+    flags: Flags<'a>,
+}
+
+impl<'a> Layer<'a> {
+    pub(crate) fn new(error: &'a (dyn error::Error + 'a), flags: Flags<'a>) -> Self {
+        Self { error, flags }
+    }
+}
+
+impl fmt::Display for Layer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // secrets are masked after localizing, in case a translation repeats
+        // them
+        write!(f, "{}", crate::redact::Redacting(&Localized(self)))?;
+        let type_name = self.flags.type_names.and_then(|names| names.of(self.error));
+        if let Some(type_name) = type_name {
+            write!(f, " ({})", type_name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Layer<'_> {
+    fn write_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "std")]
+        if let Some(localizer) = crate::localizer() {
+            let message = self.error.to_string();
+            let code = crate::report::error_code(self.error).map(|code| code.to_string());
+            if let Some(localized) = localizer(&message, code.as_deref()) {
+                // keep the fields and the location that follow the message
+                let flagged = Flagged(self).to_string();
+                let suffix = flagged.strip_prefix(message.as_str()).unwrap_or_default();
+                return write!(f, "{}{}", localized, suffix);
+            }
+        }
+        fmt::Display::fmt(&Flagged(self), f)
+    }
+}
+
+/// The message of a layer, translated by the localizer
+struct Localized<'a, 'b>(&'a Layer<'b>);
+
+impl fmt::Display for Localized<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_message(f)
+    }
+}
+
+/// The error of a layer, formatted with its flags
+struct Flagged<'a, 'b>(&'a Layer<'b>);
+
+impl fmt::Display for Flagged<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = self.0.error;
+        match (self.0.flags.alternate, self.0.flags.plus) {
+            (false, false) => write!(f, "{}", error),
+            (false, true) => write!(f, "{:+}", error),
+            (true, false) => write!(f, "{:#}", error),
+            (true, true) => write!(f, "{:+#}", error),
+        }
+    }
+}
+
+/// A layer whose continuation lines are indented, so that multi-line messages
+/// remain visually nested
+struct Indented<'a> {
+    layer: Layer<'a>,
+    indent: usize,
+}
+
+impl<'a> Layer<'a> {
+    fn indent(self, indent: usize) -> Indented<'a> {
+        Indented {
+            layer: self,
+            indent,
+        }
+    }
+}
+
+impl fmt::Display for Indented<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = IndentWriter {
+            f,
+            indent: self.indent,
+            newline: false,
+        };
+        write!(writer, "{}", self.layer)
+    }
+}
+
+/// Indents every line after a line break, and the first line if `newline` is
+/// initially true
+struct IndentWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    indent: usize,
+    newline: bool,
+}
+
+impl fmt::Write for IndentWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.f.write_char('\n')?;
+                self.newline = true;
+            }
+            // empty lines are left without trailing whitespace
+            if !line.is_empty() {
+                if self.newline {
+                    write!(self.f, "{:1$}", "", self.indent)?;
+                    self.newline = false;
+                }
+                self.f.write_str(line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn inspect_chain() {
+        let e = crate::wrap!(crate::wrap!(crate::err!("root"), "middle"), "outer");
+        let chain = crate::print_error_chain(&e);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.root().to_string(), "root");
+        let messages: Vec<String> = chain.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, ["outer", "middle", "root"]);
+        assert_eq!(chain.to_string(), "outer: middle: root");
+        assert_eq!(chain.causes().to_string(), "middle: root");
+        let e = crate::err!("root");
+        assert_eq!(crate::print_error_chain(&e).causes().to_string(), "");
+    }
+
+    #[test]
+    fn chain_format() {
+        let e = crate::wrap!(crate::wrap!(crate::err!("root"), "middle"), "outer");
+        let format = crate::ChainFormat::new().separator("\n");
+        assert_eq!(format.display(&e).to_string(), "outer\nmiddle\nroot");
+        let format = crate::ChainFormat::new().caused_by(true).indent(1);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "outer\n\nCaused by:\n 0: middle\n 1: root"
+        );
+    }
+
+    #[test]
+    fn tree() {
+        let e = crate::wrap!(crate::wrap!(crate::err!("root"), "middle"), "outer");
+        assert_eq!(
+            crate::print_error_tree(&e).to_string(),
+            "outer\n  └─ caused by: middle\n     └─ caused by: root"
+        );
+        let e = crate::err!("root");
+        assert_eq!(crate::print_error_tree(&e).to_string(), "root");
+    }
+
+    #[test]
+    fn multi_line_causes() {
+        let e = crate::wrap!(crate::err!("expected `}}`\n\n  at line 3"), "parse error");
+        let e = crate::wrap!(e, "failed to load config");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "failed to load config\n\nCaused by:\n    0: parse error\n    1: expected `}`\n\n         at line 3"
+        );
+        assert_eq!(
+            crate::print_error_tree(&e).to_string(),
+            "failed to load config\n  └─ caused by: parse error\n     └─ caused by: expected `}`\n\n                     at line 3"
+        );
+    }
+
+    #[test]
+    fn single_line() {
+        let e = crate::wrap!(crate::err!("expected `}}`\r\n\tat line 3"), "parse error");
+        let e = crate::wrap!(e, "failed to load config");
+        let format = crate::ChainFormat::new().single_line(true);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "failed to load config: parse error: expected `}`\\r\\nat line 3"
+        );
+        assert_eq!(
+            format.separator(" <- ").display(&START).to_string(),
+            "start <- first <- second <- ... (cycle detected)"
+        );
+    }
+
+    #[test]
+    fn max_causes() {
+        let e = crate::wrap!(crate::err!("root"), "third");
+        let e = crate::wrap!(crate::wrap!(e, "second"), "first");
+        let e = crate::wrap!(e, "outer");
+        let format = crate::ChainFormat::new().max_causes(2);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "outer: first: second: ... and 2 more"
+        );
+        assert_eq!(
+            format.caused_by(true).display(&e).to_string(),
+            "outer\n\nCaused by:\n    0: first\n    1: second\n       ... and 2 more"
+        );
+        assert_eq!(
+            format.max_causes(0).caused_by(true).display(&e).to_string(),
+            "outer\n\nCaused by:\n    ... and 4 more"
+        );
+        assert_eq!(
+            format.max_causes(1).tree(true).display(&e).to_string(),
+            "outer\n  └─ caused by: first\n     ... and 3 more"
+        );
+        assert_eq!(
+            format.max_causes(4).display(&e).to_string(),
+            crate::print_error_chain(&e).to_string()
+        );
+    }
+
+    #[test]
+    fn margin() {
+        let e = crate::wrap!(crate::err!("expected `}}`\n\n  at line 3"), "parse error");
+        let e = crate::wrap!(e, "failed to load config"; path = "config.toml");
+        let format = crate::ChainFormat::new().caused_by(true).margin(4);
+        assert_eq!(
+            format!("{:#}", format.display(&e)),
+            "    failed to load config (path = config.toml)\n\n    Caused by:\n        0: parse error\n        1: expected `}`\n\n             at line 3"
+        );
+        let format = crate::ChainFormat::new().margin(2);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "  failed to load config: parse error: expected `}`\n\n    at line 3"
+        );
+    }
+
+    #[test]
+    fn debug_chain() {
+        #[derive(Debug)]
+        struct Timeout {
+            seconds: u32,
+        }
+
+        impl core::fmt::Display for Timeout {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "timed out after {}s", self.seconds)
+            }
+        }
+
+        impl core::error::Error for Timeout {}
+
+        let e = crate::Report::new(Timeout { seconds: 30 }).with_help("retry");
+        assert_eq!(
+            crate::debug_error_chain(&e).to_string(),
+            "Report { error: Timeout { seconds: 30 }, notes: [], help: [\"retry\"] }"
+        );
+        let e = crate::wrap!(crate::wrap!(Timeout { seconds: 30 }, "middle"), "outer");
+        let report = format!("{:#}", crate::debug_error_chain(&e));
+        assert!(report.starts_with("FormattedWrapError {\n    message: \"outer\",\n"));
+        assert!(report.ends_with("\n    1: Timeout {\n           seconds: 30,\n       }"));
+    }
+
+    #[test]
+    fn type_names() {
+        let root = crate::wrap!(core::fmt::Error, "formatting failed");
+        let e = std::io::Error::other(root);
+        let format = crate::ChainFormat::new().type_names(true);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "formatting failed (std::io::Error): \
+             an error occurred when formatting an argument (core::fmt::Error)"
+        );
+        // the type of the outermost error is unknown, because it has been boxed
+        let e: crate::Error = crate::wrap!(e, "outer").into();
+        assert_eq!(
+            format.display(e.as_ref()).to_string(),
+            "outer: formatting failed (std::io::Error): \
+             an error occurred when formatting an argument (core::fmt::Error)"
+        );
+        assert_eq!(
+            super::short_type_name("a::error::Error<b::errors::E, [c::error::X; 2]>"),
+            "a::Error<b::E, [c::X; 2]>"
+        );
+    }
+
+    #[test]
+    fn empty_messages() {
+        let e = crate::wrap!(crate::err!("root"), "");
+        let e = crate::wrap!(crate::wrap!(e, "middle"), "");
+        let e = crate::wrap!(crate::wrap!(e, "outer"), "");
+        assert_eq!(
+            crate::print_error_chain(&e).to_string(),
+            "outer: middle: root"
+        );
+        assert_eq!(
+            crate::print_error_chain(&e).causes().to_string(),
+            "middle: root"
+        );
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer\n\nCaused by:\n    0: middle\n    1: root"
+        );
+        let format = crate::ChainFormat::new().max_causes(1);
+        assert_eq!(
+            format.display(&e).to_string(),
+            "outer: middle: ... and 1 more"
+        );
+        assert_eq!(
+            format.reverse(true).display(&e).to_string(),
+            "root: middle: ... and 1 more"
+        );
+        assert_eq!(crate::print_error_chain(&e).len(), 6);
+    }
+
+    #[test]
+    fn numbering() {
+        let mut e = crate::wrap!(crate::err!("root\nline 2"), "0");
+        for i in 1..11 {
+            e = crate::wrap!(e, "{}", i);
+        }
+        let e = crate::wrap!(e, "outer");
+        let format = crate::ChainFormat::new().caused_by(true).first_number(1);
+        let report = format.number_width(2).display(&e).to_string();
+        assert!(report.contains("\n     1: 10\n     2: 9\n"), "{}", report);
+        assert!(
+            report.ends_with("\n    12: root\n        line 2"),
+            "{}",
+            report
+        );
+        let report = format.number_alignment(core::fmt::Alignment::Center);
+        let report = report.number_width(4).display(&e).to_string();
+        assert!(report.contains("\n     1  : 10\n"), "{}", report);
+        assert!(report.contains("\n     12 : root\n"), "{}", report);
+        let report = format.reverse(true).display(&e).to_string();
+        assert!(report.contains("\n\nContext:\n    1: 0\n"), "{}", report);
+    }
+
+    #[test]
+    fn reverse() {
+        let e = crate::wrap!(crate::err!("root"), "third");
+        let e = crate::wrap!(crate::wrap!(e, "second"), "first");
+        let e = crate::wrap!(e, "outer");
+        let format = crate::ChainFormat::new().reverse(true);
+        assert_eq!(
+            format.max_causes(2).display(&e).to_string(),
+            "root: third: second: ... and 2 more"
+        );
+        assert_eq!(
+            format.max_causes(1).caused_by(true).display(&e).to_string(),
+            "root\n\nContext:\n    third\n    ... and 3 more"
+        );
+        assert_eq!(
+            format.display(&START).to_string(),
+            "... (cycle detected): second: first: start"
+        );
+        let e = crate::err!("root");
+        assert_eq!(format.caused_by(true).display(&e).to_string(), "root");
+        let e = crate::Report::new(crate::wrap!(e, "line 1\nline 2")).with_help("retry");
+        assert_eq!(
+            format.single_line(true).display(&e).to_string(),
+            "root: line 1\\nline 2"
+        );
+        assert_eq!(
+            format.caused_by(true).display(&e).to_string(),
+            "root\n\nContext:\n    line 1\n    line 2\n\nhelp: retry"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Node(&'static str, &'static Node);
+
+    impl std::fmt::Display for Node {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl std::error::Error for Node {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(self.1)
+        }
+    }
+
+    static START: Node = Node("start", &FIRST);
+    static FIRST: Node = Node("first", &SECOND);
+    static SECOND: Node = Node("second", &FIRST);
+    static SELF: Node = Node("self", &SELF);
+
+    #[test]
+    fn cycles() {
+        let chain = crate::print_error_chain(&START);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(
+            chain.to_string(),
+            "start: first: second: ... (cycle detected)"
+        );
+        assert_eq!(
+            format!("{:#}", chain),
+            "start\n\nCaused by:\n    0: first\n    1: second\n       ... (cycle detected)"
+        );
+        // the reference passed to `print_error_chain` is a separate error
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&SELF)),
+            "self\n\nCaused by:\n    self\n    ... (cycle detected)"
+        );
+        assert_eq!(
+            crate::print_error_tree(&SELF).to_string(),
+            "self\n  └─ caused by: self\n     ... (cycle detected)"
+        );
+    }
+
+    #[test]
+    fn repeated_causes() {
+        let mut e: crate::Error = crate::err!("connection refused").into();
+        for _ in 0..3 {
+            e = crate::wrap!(e, "attempt failed").into();
+        }
+        let e = crate::wrap!(e, "failed to connect");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "failed to connect\n\nCaused by:\n    0: attempt failed (repeated 3 times)\n    1: connection refused"
+        );
+        assert_eq!(
+            crate::print_error_chain(&e).to_string(),
+            "failed to connect: attempt failed: attempt failed: attempt failed: connection refused"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{fmt, process};
+
+/// Returns an error if a command exited unsuccessfully
+///
+/// ```no_run
+/// let status = std::process::Command::new("make").status()?;
+/// ees::check_status(status, "building the documentation")?;
+/// # Ok::<(), ees::Error>(())
+/// ```
+#[track_caller]
+pub fn check_status(status: process::ExitStatus, context: impl fmt::Display) -> crate::Result<()> {
+    if status.success() {
+        return Ok(());
+    }
+    let error = crate::err!("command failed with {}", status);
+    Err(crate::wrap!(error, "{}", context).into())
+}
+
+/// Like [check_status], but including the captured stderr output of the
+/// command in the error
+///
+/// ```no_run
+/// let output = std::process::Command::new("git").arg("status").output()?;
+/// ees::check_output(&output, "running git")?;
+/// # Ok::<(), ees::Error>(())
+/// ```
+#[track_caller]
+pub fn check_output(output: &process::Output, context: impl fmt::Display) -> crate::Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let error = match stderr.trim() {
+        "" => crate::err!("command failed with {}", output.status),
+        stderr => crate::err!("command failed with {}: {}", output.status, stderr),
+    };
+    Err(crate::wrap!(error, "{}", context).into())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Command;
+
+    #[test]
+    fn failed_command() {
+        let output = Command::new("sh")
+            .args(["-c", "echo 'no such branch' >&2; exit 3"])
+            .output()
+            .expect("failed to run sh");
+        let e = super::check_output(&output, "running git").unwrap_err();
+        assert_eq!(
+            crate::print_error_chain(e.as_ref()).to_string(),
+            "running git: command failed with exit status: 3: no such branch"
+        );
+        let e = super::check_status(output.status, "running git").unwrap_err();
+        assert_eq!(
+            crate::print_error_chain(e.as_ref()).to_string(),
+            "running git: command failed with exit status: 3"
+        );
+        let status = Command::new("true").status().expect("failed to run true");
+        assert!(super::check_status(status, "running true").is_ok());
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::vec::Vec;
+use core::{error, fmt, marker::PhantomData};
+
+/// An error together with secondary errors that occurred while handling it,
+/// e.g. when cleaning up after a failed operation
+///
+/// `CompositeError` is a transparent wrapper around the primary error: its
+/// message and sources are those of the primary error. The secondary errors of
+/// every `CompositeError` in the chain are printed in a separate section by the
+/// `{:#}` format of [print_error_chain](crate::print_error_chain), and when the
+/// error is returned from `main()` as a [MainError](crate::MainError).
+///
+/// ```
+/// let e = ees::CompositeError::new(ees::wrap!(ees::err!("disk full"), "failed to write file"))
+///     .with_secondary(ees::err!("failed to remove temporary file"));
+/// assert_eq!(
+///     format!("{:#}", ees::print_error_chain(&e)),
+///     "failed to write file\n\nCaused by:\n    disk full\n\n\
+///      Also failed:\n    failed to remove temporary file"
+/// );
+/// ```
+///
+/// See [defer_err!](crate::defer_err) for running cleanup code that can fail.
+pub struct CompositeError(crate::report::View<State>);
+
+#[derive(Debug)]
+pub(crate) struct State {
+    pub(crate) primary: crate::Error,
+    pub(crate) secondary: Vec<crate::Error>,
+}
+
+impl State {
+    /// Write the "Also failed:" section, after a line break
+    pub(crate) fn write_secondary(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\nAlso failed:")?;
+        for error in &self.secondary {
+            write!(f, "\n    {}", crate::print_error_chain(error.as_ref()))?;
+        }
+        Ok(())
+    }
+}
+
+impl CompositeError {
+    /// Wrap the primary error, without any secondary errors
+    ///
+    /// If the error already is a `CompositeError`, it is returned unchanged.
+    pub fn new(primary: impl Into<crate::Error>) -> Self {
+        match primary.into().downcast::<Self>() {
+            Ok(composite) => *composite,
+            Err(primary) => Self(crate::report::View(State {
+                primary,
+                secondary: Vec::new(),
+            })),
+        }
+    }
+
+    /// Add a secondary error
+    #[must_use]
+    pub fn with_secondary(mut self, error: impl Into<crate::Error>) -> Self {
+        self.0 .0.secondary.push(error.into());
+        self
+    }
+
+    /// The primary error
+    #[must_use]
+    pub fn primary(&self) -> crate::ErrorRef<'_> {
+        self.0 .0.primary.as_ref()
+    }
+
+    /// The secondary errors, in the order they were added
+    #[must_use]
+    pub fn secondary(&self) -> &[crate::Error] {
+        &self.0 .0.secondary
+    }
+}
+
+impl fmt::Debug for CompositeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = &self.0 .0;
+        f.debug_struct("CompositeError")
+            .field("primary", &state.primary)
+            .field("secondary", &state.secondary)
+            .finish()
+    }
+}
+
+impl fmt::Display for CompositeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0 .0.primary.fmt(f)
+    }
+}
+
+impl error::Error for CompositeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0 .0.primary.source()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&self.0)
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.0 .0.primary.provide(request);
+    }
+}
+
+/// Cleanup code that can fail, created with [defer_err!](crate::defer_err)
+///
+/// Call [finish](Self::finish) with the result of the guarded operation to run
+/// the cleanup and combine both errors. If the guard is dropped instead, e.g.
+/// because of an early return, the cleanup still runs, but its error is
+/// discarded.
+#[must_use = "the cleanup error is discarded unless `finish` is called"]
+pub struct Deferred<F, E>
+where
+    F: FnOnce() -> Result<(), E>,
+{
+    cleanup: Option<F>,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<F, E> Deferred<F, E>
+where
+    F: FnOnce() -> Result<(), E>,
+    E: Into<crate::Error>,
+{
+    /// Run `cleanup` when the guard is finished or dropped
+    pub fn new(cleanup: F) -> Self {
+        Self {
+            cleanup: Some(cleanup),
+            error: PhantomData,
+        }
+    }
+
+    /// Run the cleanup, and combine its error with the result of the guarded
+    /// operation
+    ///
+    /// If both fail, the cleanup error becomes a secondary error of a
+    /// [CompositeError]. If only the cleanup fails, its error is returned.
+    pub fn finish<T>(mut self, result: Result<T, impl Into<crate::Error>>) -> crate::Result<T> {
+        let cleanup =
+            self.cleanup.take().expect("the cleanup only runs once")().map_err(Into::into);
+        match (result, cleanup) {
+            (Ok(value), Ok(())) => Ok(value),
+            (Ok(_), Err(error)) => Err(error),
+            (Err(error), Ok(())) => Err(error.into()),
+            (Err(error), Err(secondary)) => {
+                Err(CompositeError::new(error).with_secondary(secondary).into())
+            }
+        }
+    }
+}
+
+impl<F, E> fmt::Debug for Deferred<F, E>
+where
+    F: FnOnce() -> Result<(), E>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deferred")
+            .field("finished", &self.cleanup.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Drop for Deferred<F, E>
+where
+    F: FnOnce() -> Result<(), E>,
+{
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    #[test]
+    fn composite() {
+        let e = super::CompositeError::new(crate::wrap!(crate::err!("root"), "outer"))
+            .with_secondary(crate::wrap!(crate::err!("busy"), "cleanup"))
+            .with_secondary(crate::err!("unlock"));
+        assert_eq!(e.to_string(), "outer");
+        assert_eq!(e.primary().to_string(), "outer");
+        assert_eq!(e.secondary().len(), 2);
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer: root");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer\n\nCaused by:\n    root\n\nAlso failed:\n    cleanup: busy\n    unlock"
+        );
+    }
+
+    #[test]
+    fn deferred() {
+        let ran = Cell::new(false);
+        let result = crate::defer_err!({
+            ran.set(true);
+            Err(crate::err!("cleanup"))
+        })
+        .finish(Ok::<_, crate::Error>(1));
+        assert!(ran.get());
+        assert_eq!(result.unwrap_err().to_string(), "cleanup");
+
+        let result = crate::defer_err!(Err(crate::err!("cleanup")))
+            .finish(Err::<(), _>(crate::err!("primary")));
+        let e = result.unwrap_err();
+        let composite = e.downcast_ref::<super::CompositeError>().unwrap();
+        assert_eq!(composite.to_string(), "primary");
+        assert_eq!(composite.secondary()[0].to_string(), "cleanup");
+
+        let ran = Cell::new(false);
+        drop(crate::defer_err!({
+            ran.set(true);
+            Ok::<(), crate::Error>(())
+        }));
+        assert!(ran.get());
+    }
+}
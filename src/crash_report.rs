@@ -0,0 +1,181 @@
+use std::{
+    fmt, fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time,
+};
+
+static PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Write a JSON crash report to a file whenever a [MainError](crate::MainError)
+/// is reported, in addition to the report printed to stderr
+///
+/// This is for orchestrators and CI systems, which can then ingest failures
+/// without parsing the output of the program. The file is replaced by every
+/// report, and contains a single object:
+///
+/// ```json
+/// {
+///   "messages": ["failed to load config", "missing field `name`"],
+///   "codes": ["E0042"],
+///   "severity": "error",
+///   "exit_code": 1,
+///   "location": {"file": "src/main.rs", "line": 12, "column": 5},
+///   "backtrace": null,
+///   "timestamp": 1791970245
+/// }
+/// ```
+///
+/// (The file is written on one line.) The messages are those of the error
+/// chain, from the outermost error to the root cause, and the location is
+/// that of the outermost error that has one (see [location](crate::location)).
+/// The timestamp is the time of the report, in seconds since the Unix epoch.
+/// The backtrace is only included with `--cfg ees_nightly`, if an error in the
+/// chain provides one.
+///
+/// Like the [Reporter](crate::Reporter), this only applies to errors that are
+/// reported through [Termination](std::process::Termination), [run](crate::run)
+/// or [exit!](crate::exit), and not to a `MainError` that is returned from
+/// `main()` directly. On Unix, the report can be written to an inherited file
+/// descriptor by passing e.g. `/dev/fd/3`.
+///
+/// ```no_run
+/// ees::set_crash_report_path("/var/run/myapp/crash.json");
+/// ```
+///
+/// If the file can't be written, a warning is printed to stderr after the
+/// report.
+pub fn set_crash_report_path(path: impl Into<PathBuf>) {
+    // a poisoned lock can't leave the path in an invalid state
+    let mut current = PATH.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(path.into());
+}
+
+/// Write the crash report of an error, if a path has been set
+pub(crate) fn write(error: &crate::MainError) {
+    let path = match PATH.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        Some(path) => path,
+        None => return,
+    };
+    let report = CrashReport {
+        error,
+        time: time::SystemTime::now(),
+    };
+    if let Err(e) = write_file(&path, &report) {
+        eprintln!(
+            "warning: failed to write the crash report to {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+fn write_file(path: &Path, report: &CrashReport<'_>) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    writeln!(file, "{}", report)?;
+    file.flush()
+}
+
+struct CrashReport<'a> {
+    error: &'a crate::MainError,
+    time: time::SystemTime,
+}
+
+impl fmt::Display for CrashReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = self.error.as_error();
+        write!(f, "{{\"messages\":[")?;
+        for (i, layer) in crate::Chain::new(error).enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            crate::internal::write_json_string(f, &crate::redact::Redacting(&layer))?;
+        }
+        write!(f, "],\"codes\":[")?;
+        let codes = crate::chain::static_chain(error).filter_map(crate::internal::error_code);
+        for (i, code) in codes.enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            crate::internal::write_json_string(f, &code)?;
+        }
+        let severity = match self.error.severity() {
+            crate::Severity::Warning => "warning",
+            crate::Severity::Error => "error",
+            crate::Severity::Fatal => "fatal",
+        };
+        write!(
+            f,
+            "],\"severity\":\"{}\",\"exit_code\":{},\"location\":",
+            severity,
+            self.error.exit_code()
+        )?;
+        let location = crate::chain::static_chain(error).find_map(crate::location);
+        match location {
+            Some(location) => {
+                write!(f, "{{\"file\":")?;
+                crate::internal::write_json_string(f, &location.file())?;
+                write!(
+                    f,
+                    ",\"line\":{},\"column\":{}}}",
+                    location.line(),
+                    location.column()
+                )?;
+            }
+            None => f.write_str("null")?,
+        }
+        f.write_str(",\"backtrace\":")?;
+        match self.error.backtrace() {
+            Some(backtrace) => crate::internal::write_json_string(f, backtrace)?,
+            None => f.write_str("null")?,
+        }
+        // times before 1970 are clamped, the clock is most likely wrong
+        let timestamp = self
+            .time
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        write!(f, ",\"timestamp\":{}}}", timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn crash_report() {
+        let root = crate::err!(code = "E0042", "missing \"name\"");
+        let (e, line) = (crate::wrap!(root, "failed"), line!());
+        let e = crate::MainError::from(e).with_exit_code(3);
+        let report = super::CrashReport {
+            error: &e,
+            time: UNIX_EPOCH + Duration::from_secs(1_791_970_245),
+        };
+        let location = format!(
+            "{{\"file\":\"src/crash_report.rs\",\"line\":{},\"column\":26}}",
+            line
+        );
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "{{\"messages\":[\"failed\",\"missing \\\"name\\\"\"],\"codes\":[\"E0042\"],\
+                 \"severity\":\"error\",\"exit_code\":3,\"location\":{},\
+                 \"backtrace\":null,\"timestamp\":1791970245}}",
+                location
+            )
+        );
+
+        let e = crate::MainError::from(std::fmt::Error);
+        let report = super::CrashReport {
+            error: &e,
+            time: UNIX_EPOCH,
+        };
+        assert_eq!(
+            report.to_string(),
+            "{\"messages\":[\"an error occurred when formatting an argument\"],\"codes\":[],\
+             \"severity\":\"error\",\"exit_code\":1,\"location\":null,\
+             \"backtrace\":null,\"timestamp\":0}"
+        );
+    }
+}
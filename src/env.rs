@@ -0,0 +1,78 @@
+//! Wrappers around [std::env](mod@std::env) that include the name of the
+//! variable in their errors
+//!
+//! ```
+//! let e = ees::env::var("EES_DOES_NOT_EXIST").unwrap_err();
+//! assert_eq!(
+//!     ees::print_error_chain(e.as_ref()).to_string(),
+//!     "failed to read environment variable EES_DOES_NOT_EXIST: environment variable not found"
+//! );
+//! ```
+//!
+//! The underlying [VarError](env::VarError) is the source of the returned
+//! error.
+
+use std::{env, ffi::OsStr, str::FromStr};
+
+/// Read an environment variable, see [env::var]
+#[track_caller]
+pub fn var(name: impl AsRef<OsStr>) -> crate::Result<String> {
+    let name = name.as_ref();
+    match env::var(name) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(
+            error,
+            "failed to read environment variable {}",
+            name.to_string_lossy()
+        )
+        .into()),
+    }
+}
+
+/// Read an environment variable and parse its value
+///
+/// ```
+/// std::env::set_var("EES_PORT", "80a");
+/// let e = ees::env::var_parsed::<u16>("EES_PORT").unwrap_err();
+/// assert_eq!(
+///     ees::print_error_chain(e.as_ref()).to_string(),
+///     "invalid value of environment variable EES_PORT: invalid digit found in string"
+/// );
+/// ```
+#[track_caller]
+pub fn var_parsed<T>(name: impl AsRef<OsStr>) -> crate::Result<T>
+where
+    T: FromStr,
+    T::Err: Into<crate::Error>,
+{
+    let name = name.as_ref();
+    let value = var(name)?;
+    match value.parse() {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(
+            error,
+            "invalid value of environment variable {}",
+            name.to_string_lossy()
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn missing_variable() {
+        let (e, line) = (super::var("EES_TEST_MISSING").unwrap_err(), line!());
+        assert_eq!(
+            e.to_string(),
+            "failed to read environment variable EES_TEST_MISSING"
+        );
+        assert!(e.source().unwrap().is::<std::env::VarError>());
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        let e = super::var_parsed::<u8>("EES_TEST_MISSING").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "failed to read environment variable EES_TEST_MISSING"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::{io, sync::RwLock};
+
+/// A table of exit codes for kinds and codes of errors, consulted by
+/// [MainError::exit_code](crate::MainError::exit_code)
+///
+/// The table is installed once with [set_exit_map], usually at the start of
+/// `main()`. For each error, the first matching rule is used, in this order:
+///
+/// 1. an exit code set with [with_exit_code](crate::MainError::with_exit_code)
+/// 2. the [ErrorCode](crate::ErrorCode) of the error, see [code](Self::code)
+/// 3. the [Kind](crate::Kind) of the error, see [kind](Self::kind)
+/// 4. an [io::Error] in the chain, see [io](Self::io)
+///
+/// Warnings always exit with code 0, and other errors with code 1 if there is
+/// no matching rule.
+///
+/// ```
+/// use ees::{ExitMap, Kind};
+///
+/// ees::set_exit_map(ExitMap::new().kind(Kind::User, 2).code("E0042", 3));
+///
+/// let e = ees::MainError::from(ees::err!(kind = Kind::User, "unknown flag --frobnicate"));
+/// assert_eq!(e.exit_code(), 2);
+/// let e = ees::MainError::from(ees::err!(code = "E0042", "missing field"));
+/// assert_eq!(e.exit_code(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExitMap {
+    codes: Vec<(crate::ErrorCode, u8)>,
+    kinds: Vec<(crate::Kind, u8)>,
+    io: Option<u8>,
+}
+
+impl ExitMap {
+    /// An empty table
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The exit codes of `sysexits.h`: 64 (`EX_USAGE`) for [User](crate::Kind::User)
+    /// errors, 66 (`EX_NOINPUT`) for [NotFound](crate::Kind::NotFound), 70
+    /// (`EX_SOFTWARE`) for [Internal](crate::Kind::Internal), 75
+    /// (`EX_TEMPFAIL`) for [Transient](crate::Kind::Transient), and 74
+    /// (`EX_IOERR`) for io errors
+    ///
+    /// More rules can be added to the returned table.
+    #[must_use]
+    pub fn sysexits() -> Self {
+        Self::new()
+            .kind(crate::Kind::User, 64)
+            .kind(crate::Kind::NotFound, 66)
+            .kind(crate::Kind::Internal, 70)
+            .kind(crate::Kind::Transient, 75)
+            .io(74)
+    }
+
+    /// Use `exit_code` for errors with the given [ErrorCode](crate::ErrorCode)
+    ///
+    /// If the table already contains the code, its exit code is replaced.
+    #[must_use]
+    pub fn code(mut self, code: impl Into<crate::ErrorCode>, exit_code: u8) -> Self {
+        let code = code.into();
+        self.codes.retain(|(existing, _)| *existing != code);
+        self.codes.push((code, exit_code));
+        self
+    }
+
+    /// Use `exit_code` for errors of the given [Kind](crate::Kind)
+    ///
+    /// If the table already contains the kind, its exit code is replaced.
+    #[must_use]
+    pub fn kind(mut self, kind: crate::Kind, exit_code: u8) -> Self {
+        self.kinds.retain(|(existing, _)| *existing != kind);
+        self.kinds.push((kind, exit_code));
+        self
+    }
+
+    /// Use `exit_code` for errors caused by an [io::Error]
+    #[must_use]
+    pub fn io(mut self, exit_code: u8) -> Self {
+        self.io = Some(exit_code);
+        self
+    }
+
+    /// The exit code for an error, if any rule matches
+    #[must_use]
+    pub fn lookup(&self, error: crate::ErrorRef<'_>) -> Option<u8> {
+        fn find<K: PartialEq>(rules: &[(K, u8)], key: Option<K>) -> Option<u8> {
+            let key = key?;
+            rules
+                .iter()
+                .find(|(existing, _)| *existing == key)
+                .map(|(_, exit_code)| *exit_code)
+        }
+
+        find(&self.codes, crate::error_code(error))
+            .or_else(|| find(&self.kinds, crate::kind_of(error)))
+            .or_else(|| {
+                let is_io = crate::chain::static_chain(error).any(|error| error.is::<io::Error>());
+                self.io.filter(|_| is_io)
+            })
+    }
+}
+
+static EXIT_MAP: RwLock<Option<ExitMap>> = RwLock::new(None);
+
+/// Install an [ExitMap], replacing the previous one
+pub fn set_exit_map(exit_map: ExitMap) {
+    // a poisoned lock can't leave the table in an invalid state
+    let mut current = EXIT_MAP.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(exit_map);
+}
+
+/// The exit code from the installed [ExitMap], if any rule matches
+pub(crate) fn exit_code(error: crate::ErrorRef<'_>) -> Option<u8> {
+    let exit_map = EXIT_MAP.read().unwrap_or_else(|e| e.into_inner());
+    exit_map.as_ref()?.lookup(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Kind;
+
+    #[test]
+    fn lookup() {
+        let map = super::ExitMap::sysexits()
+            .code(7_u32, 9)
+            .kind(Kind::User, 2);
+        let e = crate::err!(kind = Kind::User, "invalid usage");
+        assert_eq!(map.lookup(&e), Some(2));
+        let e = crate::wrap!(
+            crate::err!(code = 7, "denied"),
+            kind = Kind::Internal,
+            "failed"
+        );
+        assert_eq!(map.lookup(&e), Some(9));
+        let e = crate::wrap!(std::io::Error::other("disk full"), "failed to save");
+        assert_eq!(map.lookup(&e), Some(74));
+        assert_eq!(map.lookup(&crate::err!("unknown")), None);
+        assert_eq!(super::ExitMap::new().lookup(&e), None);
+    }
+}
@@ -0,0 +1,400 @@
+use core::{error, fmt};
+
+/// Extension methods for results with an error that can be converted into an
+/// [Error](crate::Error)
+pub trait ResultExt<T, E> {
+    /// Attach a `note:` line to the error, see [Report::with_note](crate::Report::with_note)
+    ///
+    /// ```
+    /// use ees::ResultExt;
+    ///
+    /// let result: ees::Result<()> = Err(ees::err!("file not found").into());
+    /// let e = result.note("the path is relative to the working directory").unwrap_err();
+    /// assert_eq!(
+    ///     format!("{:#}", ees::print_error_chain(&e)),
+    ///     "file not found\n\nnote: the path is relative to the working directory"
+    /// );
+    /// ```
+    fn note(self, note: impl fmt::Display) -> Result<T, crate::Report>;
+
+    /// Attach a suggestion to the error, which is printed as a `help:` line
+    /// (see [Report::with_help](crate::Report::with_help))
+    fn suggestion(self, suggestion: impl fmt::Display) -> Result<T, crate::Report>;
+
+    /// Wrap the error in a new error with the given message, like
+    /// [wrap!](crate::wrap!)
+    ///
+    /// ```
+    /// use ees::ResultExt;
+    ///
+    /// let result: Result<(), _> = Err(ees::err!("connection reset"));
+    /// let e = result.wrap_err("upload failed").unwrap_err();
+    /// assert_eq!(
+    ///     ees::print_error_chain(e.as_ref()).to_string(),
+    ///     "upload failed: connection reset"
+    /// );
+    /// ```
+    fn wrap_err(self, message: impl fmt::Display) -> crate::Result<T>;
+
+    /// Like [wrap_err](Self::wrap_err), but only creating the message if
+    /// there is an error
+    fn wrap_err_with<D: fmt::Display>(self, message: impl FnOnce() -> D) -> crate::Result<T>;
+
+    /// Run `f` on the error, if there is one, and return the result unchanged,
+    /// e.g. to count failures
+    ///
+    /// ```
+    /// use ees::ResultExt;
+    ///
+    /// let mut failures = 0;
+    /// let result: Result<(), _> = Err(ees::err!("timed out"));
+    /// let result = result.tap_err(|_| failures += 1);
+    /// assert!(result.is_err());
+    /// assert_eq!(failures, 1);
+    /// ```
+    fn tap_err(self, f: impl FnOnce(&E)) -> Self;
+
+    /// Pass the error chain to `log` and return the error, e.g. to record it
+    /// with a logging facade before propagating it
+    ///
+    /// The chain is displayed as `outer: cause: root`, or with `{:#}` as one
+    /// line per cause.
+    ///
+    /// ```ignore
+    /// use ees::ResultExt;
+    ///
+    /// let config = load_config().log_err(|chain| log::warn!("{}", chain))?;
+    /// ```
+    fn log_err(self, log: impl FnOnce(crate::ErrorChain<crate::ErrorRef<'_>>)) -> crate::Result<T>;
+
+    /// Set the [Kind](crate::Kind) of the error, without changing its
+    /// message or its sources
+    ///
+    /// ```
+    /// use ees::{Kind, ResultExt};
+    ///
+    /// let result: Result<(), _> = Err(std::io::Error::other("connection reset"));
+    /// let e = result.with_kind(Kind::Transient).unwrap_err();
+    /// assert_eq!(ees::kind_of(e.as_ref()), Some(Kind::Transient));
+    /// assert_eq!(e.to_string(), "connection reset");
+    /// ```
+    ///
+    /// Errors from other crates are wrapped in a transparent error for this,
+    /// so their type can no longer be found with e.g.
+    /// [find_cause](crate::find_cause). Their sources are kept as they are.
+    fn with_kind(self, kind: crate::Kind) -> crate::Result<T>;
+
+    /// If there is an error, try `fallback` instead, and if that fails as
+    /// well, chain its error onto the original error
+    ///
+    /// ```
+    /// use ees::ResultExt;
+    ///
+    /// let result: Result<(), _> = Err(ees::err!("primary server unreachable"));
+    /// let e = result
+    ///     .or_wrap_with(|_| Err::<(), _>(ees::err!("no cached copy")))
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     ees::print_error_chain(e.as_ref()).to_string(),
+    ///     "fallback failed: no cached copy: original failure: primary server unreachable"
+    /// );
+    /// ```
+    ///
+    /// The original error is the source of the returned error, so that e.g.
+    /// [find_cause](crate::find_cause) finds it. The chain of the fallback
+    /// error is part of the outer message.
+    fn or_wrap_with<E2: Into<crate::Error>>(
+        self,
+        fallback: impl FnOnce(&crate::Error) -> Result<T, E2>,
+    ) -> crate::Result<T>;
+}
+
+impl<T, E: Into<crate::Error>> ResultExt<T, E> for Result<T, E> {
+    fn note(self, note: impl fmt::Display) -> Result<T, crate::Report> {
+        self.map_err(|error| crate::Report::new(error).with_note(note))
+    }
+
+    fn suggestion(self, suggestion: impl fmt::Display) -> Result<T, crate::Report> {
+        self.map_err(|error| crate::Report::new(error).with_help(suggestion))
+    }
+
+    #[track_caller]
+    fn wrap_err(self, message: impl fmt::Display) -> crate::Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(crate::wrap!(error, "{}", message).into()),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_with<D: fmt::Display>(self, message: impl FnOnce() -> D) -> crate::Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(crate::wrap!(error, "{}", message()).into()),
+        }
+    }
+
+    fn tap_err(self, f: impl FnOnce(&E)) -> Self {
+        if let Err(error) = &self {
+            f(error);
+        }
+        self
+    }
+
+    fn log_err(self, log: impl FnOnce(crate::ErrorChain<crate::ErrorRef<'_>>)) -> crate::Result<T> {
+        self.map_err(|error| {
+            let error = error.into();
+            log(crate::print_error_chain(error.as_ref()));
+            error
+        })
+    }
+
+    fn with_kind(self, kind: crate::Kind) -> crate::Result<T> {
+        self.map_err(|error| crate::internal::with_kind(error.into(), kind))
+    }
+
+    fn or_wrap_with<E2: Into<crate::Error>>(
+        self,
+        fallback: impl FnOnce(&crate::Error) -> Result<T, E2>,
+    ) -> crate::Result<T> {
+        let original = match self {
+            Ok(value) => return Ok(value),
+            Err(error) => error.into(),
+        };
+        fallback(&original).map_err(|fallback| {
+            let fallback = fallback.into();
+            FallbackError { fallback, original }.into()
+        })
+    }
+}
+
+#[derive(Debug)]
+struct FallbackError {
+    fallback: crate::Error,
+    original: crate::Error,
+}
+
+impl fmt::Display for FallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fallback failed: {}: original failure",
+            crate::print_error_chain(self.fallback.as_ref())
+        )
+    }
+}
+
+impl error::Error for FallbackError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.original.as_ref())
+    }
+}
+
+/// Methods for [Error](crate::Error), which is a type alias and so can't have
+/// inherent methods
+///
+/// ```
+/// use ees::ErrorExt;
+///
+/// let e: ees::Error = ees::err!("connection reset").into();
+/// let e = e.wrap("upload failed");
+/// assert_eq!(e.chain().to_string(), "upload failed: connection reset");
+/// assert_eq!(e.root_cause().to_string(), "connection reset");
+/// ```
+pub trait ErrorExt {
+    /// The complete error chain, see [print_error_chain](crate::print_error_chain)
+    fn chain(&self) -> crate::ErrorChain<crate::ErrorRef<'_>>;
+
+    /// The innermost error in the chain
+    fn root_cause(&self) -> crate::ErrorRef<'_>;
+
+    /// Wrap this error in a new error with the given message, like
+    /// [wrap!](crate::wrap!)
+    fn wrap(self, message: impl fmt::Display) -> crate::Error;
+
+    /// The outermost error in the chain of type `E`, see
+    /// [find_cause](crate::find_cause)
+    fn find_cause<E: error::Error + 'static>(&self) -> Option<&E>;
+}
+
+impl ErrorExt for crate::Error {
+    fn chain(&self) -> crate::ErrorChain<crate::ErrorRef<'_>> {
+        crate::print_error_chain(self.as_ref())
+    }
+
+    fn root_cause(&self) -> crate::ErrorRef<'_> {
+        crate::chain::static_chain(self.as_ref())
+            .last()
+            .expect("an error chain is never empty")
+    }
+
+    #[track_caller]
+    fn wrap(self, message: impl fmt::Display) -> crate::Error {
+        crate::wrap!(self, "{}", message).into()
+    }
+
+    fn find_cause<E: error::Error + 'static>(&self) -> Option<&E> {
+        crate::find_cause(self.as_ref())
+    }
+}
+
+/// Extension methods for [io::Result](std::io::Result), for I/O operations
+/// that aren't covered by the [fs](crate::fs) module
+#[cfg(feature = "std")]
+pub trait PathResultExt<T> {
+    /// Wrap the error with the path it relates to
+    ///
+    /// ```
+    /// use ees::PathResultExt;
+    ///
+    /// let e = std::fs::File::open("missing.txt").with_path("missing.txt").unwrap_err();
+    /// assert_eq!(e.to_string(), "missing.txt");
+    /// ```
+    fn with_path(self, path: impl AsRef<std::path::Path>) -> crate::Result<T>;
+}
+
+#[cfg(feature = "std")]
+impl<T> PathResultExt<T> for std::io::Result<T> {
+    #[track_caller]
+    fn with_path(self, path: impl AsRef<std::path::Path>) -> crate::Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(crate::wrap!(error, "{}", path.as_ref().display()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ResultExt;
+    use alloc::string::{String, ToString};
+
+    #[test]
+    fn attachments() {
+        let result: crate::Result<()> = Err(crate::wrap!(crate::err!("root"), "outer").into());
+        let e = result
+            .suggestion("try running with --force")
+            .note("the file is locked")
+            .unwrap_err();
+        assert_eq!(e.notes(), ["the file is locked"]);
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer: root");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer\n\nCaused by:\n    root\n\nnote: the file is locked\nhelp: try running with --force"
+        );
+    }
+
+    #[test]
+    fn log_err() {
+        let mut logged = String::new();
+        let result: Result<(), _> = Err(crate::wrap!(crate::err!("root"), "outer"));
+        let e = result
+            .log_err(|chain| logged = chain.to_string())
+            .unwrap_err();
+        assert_eq!(logged, "outer: root");
+        assert_eq!(e.to_string(), "outer");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn with_path() {
+        use crate::PathResultExt;
+
+        let result = std::fs::read("no/such/file").with_path("no/such/file");
+        let e = result.unwrap_err();
+        let chain = crate::print_error_chain(e.as_ref()).to_string();
+        assert!(chain.starts_with("no/such/file: "), "{}", chain);
+        assert!(e.source().unwrap().is::<std::io::Error>());
+    }
+
+    #[test]
+    fn wrap_err() {
+        let result: Result<(), _> = Err(crate::err!("root"));
+        let (e, line) = (result.wrap_err("outer").unwrap_err(), line!());
+        let w = crate::wrap!(crate::err!("root"), "outer");
+        assert_eq!(e.to_string(), w.to_string());
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(e.as_ref())),
+            format!("{:#}", crate::print_error_chain(&w))
+        );
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        let result: Result<(), _> = Err(crate::err!("root"));
+        let e = result.wrap_err_with(|| 1 + 1).unwrap_err();
+        assert_eq!(crate::print_error_chain(e.as_ref()).to_string(), "2: root");
+    }
+
+    #[test]
+    fn tap_err() {
+        let mut chain = String::new();
+        let result: Result<(), _> = Err(crate::wrap!(crate::err!("root"), "outer"));
+        let result = result.tap_err(|e| chain = crate::print_error_chain(e).to_string());
+        assert_eq!(chain, "outer: root");
+        assert_eq!(result.unwrap_err().to_string(), "outer");
+        let result: Result<i32, crate::Error> = Ok(1);
+        assert_eq!(result.tap_err(|_| unreachable!()).unwrap(), 1);
+    }
+
+    #[test]
+    fn with_kind() {
+        use crate::Kind;
+
+        let result: Result<(), _> = Err(crate::err!(kind = Kind::User, code = 7, "invalid"));
+        let e = result.with_kind(Kind::Internal).unwrap_err();
+        assert_eq!(crate::kind_of(e.as_ref()), Some(Kind::Internal));
+        assert_eq!(
+            crate::error_code(e.as_ref()),
+            Some(crate::ErrorCode::Number(7))
+        );
+
+        let e = crate::wrap!(std::fmt::Error, "render failed");
+        let e = Err::<(), _>(e).with_kind(Kind::Transient).unwrap_err();
+        assert_eq!(crate::kind_of(e.as_ref()), Some(Kind::Transient));
+        assert!(crate::find_cause::<std::fmt::Error>(e.as_ref()).is_some());
+
+        let e = crate::DeserializedError::from_messages(["outer", "root"]).unwrap();
+        let e = Err::<(), _>(e).with_kind(Kind::NotFound).unwrap_err();
+        assert_eq!(crate::kind_of(e.as_ref()), Some(Kind::NotFound));
+        assert_eq!(crate::chain_messages(e.as_ref()), ["outer", "root"]);
+    }
+
+    #[test]
+    fn or_wrap_with() {
+        let result: Result<i32, _> = Err(crate::err!("root"));
+        assert_eq!(
+            result.or_wrap_with(|_| Ok::<_, crate::Error>(2)).unwrap(),
+            2
+        );
+
+        let result: Result<i32, _> = Err(crate::wrap!(std::fmt::Error, "render failed"));
+        let e = result
+            .or_wrap_with(|e| Err(crate::wrap!(crate::err!("{}", e), "retry")))
+            .unwrap_err();
+        assert_eq!(
+            crate::chain_messages(e.as_ref()),
+            [
+                "fallback failed: retry: render failed: original failure",
+                "render failed",
+                "an error occurred when formatting an argument"
+            ]
+        );
+        assert!(crate::find_cause::<std::fmt::Error>(e.as_ref()).is_some());
+    }
+
+    #[test]
+    fn error_ext() {
+        use crate::ErrorExt;
+
+        let e: crate::Error = crate::err!("root").into();
+        let (e, line) = (e.wrap("middle").wrap("outer"), line!());
+        assert_eq!(
+            crate::chain_messages(e.as_ref()),
+            ["outer", "middle", "root"]
+        );
+        assert_eq!(e.chain().len(), 3);
+        assert_eq!(e.root_cause().to_string(), "root");
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        assert!(e.find_cause::<std::fmt::Error>().is_none());
+        let e = crate::Error::from(std::fmt::Error).wrap("outer");
+        assert!(e.find_cause::<std::fmt::Error>().is_some());
+    }
+}
@@ -0,0 +1,154 @@
+//! The "last error" pattern for C APIs
+//!
+//! Functions exported to C usually can't return a Rust error, so they return
+//! a sentinel value (e.g. `-1` or a null pointer) and store the error in a
+//! thread-local, from where the caller can retrieve the message:
+//!
+//! ```
+//! use std::os::raw::{c_char, c_int};
+//!
+//! #[no_mangle]
+//! pub extern "C" fn mylib_parse(value: c_int) -> c_int {
+//!     match parse(value) {
+//!         Ok(value) => value,
+//!         Err(error) => {
+//!             ees::ffi::set_last_error(error);
+//!             -1
+//!         }
+//!     }
+//! }
+//!
+//! fn parse(value: c_int) -> ees::Result<c_int> {
+//!     if value < 0 {
+//!         ees::bail!("negative value {}", value);
+//!     }
+//!     Ok(value * 2)
+//! }
+//!
+//! #[no_mangle]
+//! pub extern "C" fn mylib_last_error_length() -> c_int {
+//!     ees::ffi::last_error_length()
+//! }
+//!
+//! /// # Safety
+//! ///
+//! /// `buffer` must be null, or valid for writes of `length` bytes.
+//! #[no_mangle]
+//! pub unsafe extern "C" fn mylib_last_error_message(buffer: *mut c_char, length: c_int) -> c_int {
+//!     unsafe { ees::ffi::copy_last_error_message(buffer, length) }
+//! }
+//! ```
+//!
+//! On the C side, the message is copied into a buffer of the size returned by
+//! [last_error_length]:
+//!
+//! ```c
+//! if (mylib_parse(-1) < 0) {
+//!     int length = mylib_last_error_length();
+//!     char *message = malloc(length);
+//!     mylib_last_error_message(message, length);
+//!     fprintf(stderr, "error: %s\n", message);
+//!     free(message);
+//! }
+//! ```
+//!
+//! The message is the error chain separated with colons, as printed by
+//! [print_error_chain](crate::print_error_chain). This module doesn't export
+//! any `extern "C"` functions itself: symbols with the same names from
+//! different versions of this crate would fail to link, and the functions
+//! should use the prefix of the library that exposes them.
+
+use std::{cell::RefCell, convert::TryFrom, os::raw::c_char, os::raw::c_int, ptr};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<crate::Error>> = const { RefCell::new(None) };
+}
+
+/// Store an error as the last error of the current thread, replacing the
+/// previous one
+pub fn set_last_error(error: impl Into<crate::Error>) {
+    let error = error.into();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(error));
+}
+
+/// Remove and return the last error of the current thread
+pub fn take_last_error() -> Option<crate::Error> {
+    LAST_ERROR.with(|last_error| last_error.borrow_mut().take())
+}
+
+fn last_error_message() -> Option<String> {
+    LAST_ERROR.with(|last_error| {
+        let last_error = last_error.borrow();
+        let error = last_error.as_ref()?;
+        Some(crate::print_error_chain(error.as_ref()).to_string())
+    })
+}
+
+/// The size of the buffer needed for the message of the last error, including
+/// the terminating NUL byte, or 0 if there is no error
+#[must_use]
+pub fn last_error_length() -> c_int {
+    last_error_message().map_or(0, |message| {
+        c_int::try_from(message.len() + 1).unwrap_or(c_int::MAX)
+    })
+}
+
+/// Copy the message of the last error into `buffer` as a NUL-terminated
+/// string, and return the number of bytes written excluding the NUL byte
+///
+/// Returns 0 if there is no error, and -1 without writing anything if the
+/// buffer is null or shorter than [last_error_length]. NUL bytes within
+/// the message are replaced with spaces. The error remains stored, so that it
+/// can be read again.
+///
+/// # Safety
+///
+/// `buffer` must be null, or valid for writes of `length` bytes.
+pub unsafe fn copy_last_error_message(buffer: *mut c_char, length: c_int) -> c_int {
+    let message = match last_error_message() {
+        Some(message) => message,
+        None => return 0,
+    };
+    let written = match c_int::try_from(message.len()) {
+        Ok(written) if !buffer.is_null() && written < length => written,
+        _ => return -1,
+    };
+    let message = message.replace('\0', " ");
+    // SAFETY: the caller guarantees that `buffer` is valid for `length`
+    // bytes, and `message.len() + 1 <= length`
+    unsafe {
+        ptr::copy_nonoverlapping(message.as_ptr().cast::<c_char>(), buffer, message.len());
+        *buffer.add(message.len()) = 0;
+    }
+    written
+}
+
+/// Discard the last error of the current thread
+pub fn clear_last_error() {
+    take_last_error();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    #[test]
+    fn last_error() {
+        assert_eq!(super::last_error_length(), 0);
+        super::set_last_error(crate::wrap!(crate::err!("root"), "outer"));
+        assert_eq!(super::last_error_length(), 12);
+        let mut buffer = vec![1; 12];
+        let written = unsafe { super::copy_last_error_message(buffer.as_mut_ptr(), 11) };
+        assert_eq!(written, -1);
+        let written = unsafe { super::copy_last_error_message(buffer.as_mut_ptr(), 12) };
+        assert_eq!(written, 11);
+        let message = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+        assert_eq!(message.to_str().unwrap(), "outer: root");
+        super::clear_last_error();
+        assert!(super::take_last_error().is_none());
+        assert_eq!(
+            unsafe { super::copy_last_error_message(buffer.as_mut_ptr(), 12) },
+            0
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Wrappers around [std::fs] that include the path in their errors
+//!
+//! ```
+//! let e = ees::fs::read_to_string("does-not-exist.toml").unwrap_err();
+//! assert!(e.to_string().starts_with("failed to read does-not-exist.toml"));
+//! ```
+//!
+//! The underlying [io::Error] is the source of the returned error, so its
+//! [ErrorKind](io::ErrorKind) can still be inspected with `downcast_ref`.
+
+use std::{fs, io, path::Path};
+
+#[track_caller]
+fn wrap<T>(result: io::Result<T>, action: &str, path: &Path) -> crate::Result<T> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(error, "failed to {} {}", action, path.display()).into()),
+    }
+}
+
+#[track_caller]
+fn wrap2<T>(result: io::Result<T>, action: &str, from: &Path, to: &Path) -> crate::Result<T> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(
+            error,
+            "failed to {} {} to {}",
+            action,
+            from.display(),
+            to.display()
+        )
+        .into()),
+    }
+}
+
+/// Open a file in read-only mode, see [File::open](fs::File::open)
+#[track_caller]
+pub fn open(path: impl AsRef<Path>) -> crate::Result<fs::File> {
+    let path = path.as_ref();
+    wrap(fs::File::open(path), "open", path)
+}
+
+/// Create or truncate a file, see [File::create](fs::File::create)
+#[track_caller]
+pub fn create(path: impl AsRef<Path>) -> crate::Result<fs::File> {
+    let path = path.as_ref();
+    wrap(fs::File::create(path), "create", path)
+}
+
+/// Read the contents of a file, see [fs::read]
+#[track_caller]
+pub fn read(path: impl AsRef<Path>) -> crate::Result<Vec<u8>> {
+    let path = path.as_ref();
+    wrap(fs::read(path), "read", path)
+}
+
+/// Read the contents of a file into a string, see [fs::read_to_string]
+#[track_caller]
+pub fn read_to_string(path: impl AsRef<Path>) -> crate::Result<String> {
+    let path = path.as_ref();
+    wrap(fs::read_to_string(path), "read", path)
+}
+
+/// Write the contents of a file, see [fs::write]
+#[track_caller]
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> crate::Result<()> {
+    let path = path.as_ref();
+    wrap(fs::write(path, contents), "write", path)
+}
+
+/// Iterate over the entries of a directory, see [fs::read_dir]
+#[track_caller]
+pub fn read_dir(path: impl AsRef<Path>) -> crate::Result<fs::ReadDir> {
+    let path = path.as_ref();
+    wrap(fs::read_dir(path), "read directory", path)
+}
+
+/// Query the metadata of a file, see [fs::metadata]
+#[track_caller]
+pub fn metadata(path: impl AsRef<Path>) -> crate::Result<fs::Metadata> {
+    let path = path.as_ref();
+    wrap(fs::metadata(path), "read metadata of", path)
+}
+
+/// Create a directory and all of its parents, see [fs::create_dir_all]
+#[track_caller]
+pub fn create_dir_all(path: impl AsRef<Path>) -> crate::Result<()> {
+    let path = path.as_ref();
+    wrap(fs::create_dir_all(path), "create directory", path)
+}
+
+/// Remove a file, see [fs::remove_file]
+#[track_caller]
+pub fn remove_file(path: impl AsRef<Path>) -> crate::Result<()> {
+    let path = path.as_ref();
+    wrap(fs::remove_file(path), "remove", path)
+}
+
+/// Remove a directory and all of its contents, see [fs::remove_dir_all]
+#[track_caller]
+pub fn remove_dir_all(path: impl AsRef<Path>) -> crate::Result<()> {
+    let path = path.as_ref();
+    wrap(fs::remove_dir_all(path), "remove directory", path)
+}
+
+/// Copy the contents of a file, see [fs::copy]
+#[track_caller]
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> crate::Result<u64> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    wrap2(fs::copy(from, to), "copy", from, to)
+}
+
+/// Rename a file or directory, see [fs::rename]
+#[track_caller]
+pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> crate::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    wrap2(fs::rename(from, to), "rename", from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn path_in_error() {
+        let (e, line) = (super::read("no/such/file").unwrap_err(), line!());
+        assert_eq!(e.to_string(), "failed to read no/such/file");
+        let io_error = e.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(io_error.unwrap().kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        let e = super::rename("no/such/file", "other").unwrap_err();
+        assert_eq!(e.to_string(), "failed to rename no/such/file to other");
+    }
+}
@@ -0,0 +1,170 @@
+use core::{
+    fmt,
+    future::Future,
+    panic,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Extension methods for futures that resolve to a result with an error that
+/// can be converted into an [Error](crate::Error)
+///
+/// These work with any executor, and avoid a `map_err` after every `.await`:
+///
+/// ```
+/// use ees::FutureExt;
+///
+/// async fn connect() -> ees::Result<()> {
+///     Err(ees::err!("connection refused").into())
+/// }
+///
+/// async fn run() -> ees::Result<()> {
+///     connect().wrap_err("failed to connect to the database").await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// The location of the new error is the call to `wrap_err`, rather than the
+/// place where the future is polled.
+pub trait FutureExt<T, E>: Future<Output = Result<T, E>> + Sized {
+    /// Wrap the error in a new error with the given message when the future
+    /// resolves, like [ResultExt::wrap_err](crate::ResultExt::wrap_err)
+    #[track_caller]
+    fn wrap_err<D: fmt::Display>(self, message: D) -> WrapErr<Self, D>;
+
+    /// Like [wrap_err](Self::wrap_err), but only creating the message if
+    /// there is an error
+    #[track_caller]
+    fn wrap_err_with<D: fmt::Display, F: FnOnce() -> D>(self, message: F) -> WrapErrWith<Self, F>;
+}
+
+impl<T, E: Into<crate::Error>, Fut: Future<Output = Result<T, E>>> FutureExt<T, E> for Fut {
+    #[track_caller]
+    fn wrap_err<D: fmt::Display>(self, message: D) -> WrapErr<Self, D> {
+        WrapErr {
+            future: self,
+            message: Some(message),
+            location: panic::Location::caller(),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_with<D: fmt::Display, F: FnOnce() -> D>(self, message: F) -> WrapErrWith<Self, F> {
+        WrapErrWith {
+            future: self,
+            message: Some(message),
+            location: panic::Location::caller(),
+        }
+    }
+}
+
+/// The future returned by [FutureExt::wrap_err]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WrapErr<Fut, D> {
+    future: Fut,
+    message: Option<D>,
+    location: &'static panic::Location<'static>,
+}
+
+impl<T, E, Fut, D> Future for WrapErr<Fut, D>
+where
+    E: Into<crate::Error>,
+    Fut: Future<Output = Result<T, E>>,
+    D: fmt::Display,
+{
+    type Output = crate::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is structurally pinned, and is never moved out of
+        // the wrapper. The other fields are never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let error = match future.poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Ok(value)) => return Poll::Ready(Ok(value)),
+            Poll::Ready(Err(error)) => error,
+        };
+        let message = this.message.take().expect("polled after completion");
+        let args = format_args!("{}", message);
+        Poll::Ready(Err(crate::internal::wrap_error_at(
+            error,
+            args,
+            this.location,
+        )))
+    }
+}
+
+/// The future returned by [FutureExt::wrap_err_with]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WrapErrWith<Fut, F> {
+    future: Fut,
+    message: Option<F>,
+    location: &'static panic::Location<'static>,
+}
+
+impl<T, E, Fut, D, F> Future for WrapErrWith<Fut, F>
+where
+    E: Into<crate::Error>,
+    Fut: Future<Output = Result<T, E>>,
+    D: fmt::Display,
+    F: FnOnce() -> D,
+{
+    type Output = crate::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: see `WrapErr`
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let error = match future.poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Ok(value)) => return Poll::Ready(Ok(value)),
+            Poll::Ready(Err(error)) => error,
+        };
+        let message = this.message.take().expect("polled after completion")();
+        let args = format_args!("{}", message);
+        Poll::Ready(Err(crate::internal::wrap_error_at(
+            error,
+            args,
+            this.location,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FutureExt;
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_err() {
+        async fn fail() -> Result<(), crate::Error> {
+            Err(crate::err!("root").into())
+        }
+
+        let (future, line) = (fail().wrap_err("outer"), line!());
+        let e = block_on(future).unwrap_err();
+        assert_eq!(crate::chain_messages(e.as_ref()), ["outer", "root"]);
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+
+        let e = block_on(fail().wrap_err_with(|| "lazy")).unwrap_err();
+        assert_eq!(crate::chain_messages(e.as_ref()), ["lazy", "root"]);
+        let future = async { Ok::<_, crate::Error>(1) }.wrap_err_with(|| -> &str { panic!() });
+        assert_eq!(block_on(future).unwrap(), 1);
+    }
+}
@@ -0,0 +1,251 @@
+use core::fmt;
+
+/// An error paired with the HTTP status it should be reported with, for use
+/// as the error type of web handlers
+///
+/// The status is taken from the outermost error in the chain created with
+/// `err!(status = ..., "...")`. Otherwise it is chosen based on the
+/// [Kind](crate::Kind) of the error: 400 for `User`, 503 for `Transient`,
+/// 404 for `NotFound` and 500 for `Internal` or no kind. This crate doesn't depend
+/// on any web framework, so the response conversion is a few lines in the
+/// application, e.g. for `axum`:
+///
+/// ```ignore
+/// use axum::{http::StatusCode, response::{IntoResponse, Response}};
+///
+/// struct AppError(ees::HttpError);
+///
+/// impl<E: Into<ees::Error>> From<E> for AppError {
+///     fn from(error: E) -> Self {
+///         Self(ees::HttpError::from(error))
+///     }
+/// }
+///
+/// impl IntoResponse for AppError {
+///     fn into_response(self) -> Response {
+///         let status = StatusCode::from_u16(self.0.status())
+///             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+///         (status, self.0.body().to_string()).into_response()
+///     }
+/// }
+/// ```
+///
+/// or for `actix-web`:
+///
+/// ```ignore
+/// use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+///
+/// #[derive(Debug)]
+/// struct AppError(ees::HttpError);
+///
+/// impl std::fmt::Display for AppError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "{}", self.0.body())
+///     }
+/// }
+///
+/// impl ResponseError for AppError {
+///     fn status_code(&self) -> StatusCode {
+///         StatusCode::from_u16(self.0.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+///     }
+///
+///     fn error_response(&self) -> HttpResponse {
+///         log::error!("{:#}", ees::print_error_chain(self.0.as_error()));
+///         HttpResponse::build(self.status_code()).body(self.0.body().to_string())
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HttpError {
+    error: crate::Error,
+    status: u16,
+}
+
+impl HttpError {
+    /// Override the status of this error
+    #[must_use]
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Choose the status based on the error chain, e.g. to map errors from
+    /// other crates that can't be tagged with `err!(status = ...)`
+    ///
+    /// The status is only changed if `map` returns `Some`.
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// fn status(error: ees::ErrorRef<'_>) -> Option<u16> {
+    ///     match error.downcast_ref::<io::Error>()?.kind() {
+    ///         io::ErrorKind::NotFound => Some(404),
+    ///         io::ErrorKind::PermissionDenied => Some(403),
+    ///         _ => None,
+    ///     }
+    /// }
+    ///
+    /// let e = io::Error::new(io::ErrorKind::NotFound, "no such file");
+    /// let e = ees::HttpError::from(e).map_status(status);
+    /// assert_eq!(e.status(), 404);
+    /// ```
+    #[must_use]
+    pub fn map_status(mut self, map: impl FnOnce(crate::ErrorRef<'_>) -> Option<u16>) -> Self {
+        if let Some(status) = map(self.error.as_ref()) {
+            self.status = status;
+        }
+        self
+    }
+
+    /// The HTTP status code this error should be reported with
+    #[must_use]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// The gRPC status code corresponding to the HTTP status, following the
+    /// mapping of `google.rpc.Code`
+    ///
+    /// This can be used for building a `tonic::Status`:
+    ///
+    /// ```ignore
+    /// fn to_status(error: ees::Error) -> tonic::Status {
+    ///     let error = ees::HttpError::from(error);
+    ///     tonic::Status::new(error.grpc_code().into(), error.body().to_string())
+    /// }
+    ///
+    /// fn from_status(status: tonic::Status) -> ees::Error {
+    ///     let http_status = ees::HttpError::http_status_for_grpc(status.code() as i32);
+    ///     ees::err!(status = http_status, "{}", status.message()).into()
+    /// }
+    /// ```
+    #[must_use]
+    pub fn grpc_code(&self) -> i32 {
+        match self.status {
+            200..=299 => 0,  // OK
+            400 => 3,        // INVALID_ARGUMENT
+            401 => 16,       // UNAUTHENTICATED
+            403 => 7,        // PERMISSION_DENIED
+            404 => 5,        // NOT_FOUND
+            409 => 10,       // ABORTED
+            412 => 9,        // FAILED_PRECONDITION
+            416 => 11,       // OUT_OF_RANGE
+            429 => 8,        // RESOURCE_EXHAUSTED
+            499 => 1,        // CANCELLED
+            501 => 12,       // UNIMPLEMENTED
+            503 => 14,       // UNAVAILABLE
+            504 => 4,        // DEADLINE_EXCEEDED
+            500..=599 => 13, // INTERNAL
+            _ => 2,          // UNKNOWN
+        }
+    }
+
+    /// The HTTP status corresponding to a gRPC status code, the reverse of
+    /// [grpc_code](Self::grpc_code)
+    #[must_use]
+    pub fn http_status_for_grpc(code: i32) -> u16 {
+        match code {
+            0 => 200,
+            1 => 499,
+            3 | 11 => 400,
+            4 => 504,
+            5 => 404,
+            6 | 10 => 409,
+            7 => 403,
+            8 => 429,
+            9 => 412,
+            12 => 501,
+            14 => 503,
+            16 => 401,
+            _ => 500,
+        }
+    }
+
+    /// The response body for this error
+    ///
+    /// In debug builds this is the complete error chain in the `{:#}` format
+    /// of [print_error_chain](crate::print_error_chain). Release builds only
+    /// include the message of the outermost error, so that internal details
+    /// aren't exposed to clients.
+    #[must_use]
+    pub fn body(&self) -> impl fmt::Display + '_ {
+        Body(self)
+    }
+
+    /// The wrapped error
+    #[must_use]
+    pub fn as_error(&self) -> crate::ErrorRef<'_> {
+        self.error.as_ref()
+    }
+
+    /// Convert this back into the wrapped error
+    #[must_use]
+    pub fn into_error(self) -> crate::Error {
+        self.error
+    }
+}
+
+impl<E: Into<crate::Error>> From<E> for HttpError {
+    fn from(error: E) -> Self {
+        let error = error.into();
+        let status =
+            http_status(error.as_ref()).unwrap_or_else(|| match crate::kind_of(error.as_ref()) {
+                Some(crate::Kind::User) => 400,
+                Some(crate::Kind::Transient) => 503,
+                Some(crate::Kind::NotFound) => 404,
+                Some(crate::Kind::Internal) | None => 500,
+            });
+        Self { error, status }
+    }
+}
+
+struct Body<'a>(&'a HttpError);
+
+impl fmt::Display for Body<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if cfg!(debug_assertions) {
+            write!(f, "{:#}", crate::print_error_chain(self.0.error.as_ref()))
+        } else {
+            write!(f, "{}", self.0.error)
+        }
+    }
+}
+
+/// Returns the HTTP status set with `err!(status = ..., "...")` on the
+/// outermost error in the chain that has one
+///
+/// ```
+/// let e = ees::wrap!(ees::err!(status = 404, "no such user"), "lookup failed");
+/// assert_eq!(ees::http_status(&e), Some(404));
+/// ```
+#[must_use]
+pub fn http_status(error: crate::ErrorRef<'_>) -> Option<u16> {
+    crate::chain::static_chain(error).find_map(crate::internal::http_status)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn status() {
+        let e = crate::HttpError::from(crate::err!(status = 403, "forbidden"));
+        assert_eq!(e.status(), 403);
+        assert_eq!(e.body().to_string(), "forbidden");
+        let e = crate::HttpError::from(crate::wrap!(crate::err!("timed out"), "query failed"));
+        assert_eq!(e.status(), 500);
+        let e = e.with_status(503);
+        assert_eq!(e.status(), 503);
+    }
+
+    #[test]
+    fn grpc_codes() {
+        for status in [400, 401, 403, 404, 409, 412, 429, 499, 501, 503, 504] {
+            let e = crate::HttpError::from(crate::err!("failed")).with_status(status);
+            assert_eq!(
+                crate::HttpError::http_status_for_grpc(e.grpc_code()),
+                status
+            );
+        }
+        let e = crate::HttpError::from(crate::err!("failed"));
+        assert_eq!(e.grpc_code(), 13);
+    }
+}
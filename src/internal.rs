@@ -1,83 +1,934 @@
-use std::{borrow, error, fmt};
+use crate::report::View;
+use alloc::{borrow::Cow, boxed::Box, format, string::String, string::ToString, vec::Vec};
+use core::{any, error, fmt, fmt::Write as _, panic};
+#[cfg(feature = "std")]
+use std::process;
+
+// `{:+}` opts into appending the call site, e.g. "failed to do work (src/main.rs:42)"
+fn write_location(f: &mut fmt::Formatter<'_>, location: &panic::Location<'_>) -> fmt::Result {
+    if f.sign_plus() {
+        write!(f, " ({}:{})", location.file(), location.line())?;
+    }
+    Ok(())
+}
 
 #[derive(Debug)]
 struct FormattedError {
-    message: borrow::Cow<'static, str>,
+    message: Message,
+    location: &'static panic::Location<'static>,
+    severity: Option<crate::Severity>,
+    code: Option<View<crate::ErrorCode>>,
+    status: Option<u16>,
+    kind: Option<crate::Kind>,
+    #[cfg(feature = "error-ids")]
+    id: crate::ErrorId,
 }
 
 impl fmt::Display for FormattedError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        write!(f, "{}", self.message)?;
+        write_location(f, self.location)
     }
 }
 
-impl error::Error for FormattedError {}
+impl error::Error for FormattedError {
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.code.as_ref().map(|code| code as _)
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        request.provide_ref::<panic::Location<'static>>(self.location);
+    }
+}
 
 #[inline]
 #[must_use]
+#[track_caller]
 pub fn error_from_args(args: fmt::Arguments<'_>) -> impl error::Error + Send + Sync + 'static {
-    FormattedError {
-        message: if let Some(message) = args.as_str() {
-            borrow::Cow::Borrowed(message)
-        } else {
-            borrow::Cow::Owned(fmt::format(args))
-        },
+    ErrorBuilder::new().build(args)
+}
+
+/// Collects the optional settings of `err!`, e.g. `err!(@warn, "...")`
+#[derive(Default)]
+pub struct ErrorBuilder {
+    severity: Option<crate::Severity>,
+    code: Option<crate::ErrorCode>,
+    status: Option<u16>,
+    kind: Option<crate::Kind>,
+}
+
+impl ErrorBuilder {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn severity(mut self, severity: crate::Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn code(mut self, code: impl Into<crate::ErrorCode>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn kind(mut self, kind: crate::Kind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub fn build(self, args: fmt::Arguments<'_>) -> impl error::Error + Send + Sync + 'static {
+        self.build_message(format_message(args))
+    }
+
+    #[track_caller]
+    fn build_message(self, message: Message) -> FormattedError {
+        let error = FormattedError {
+            message,
+            location: panic::Location::caller(),
+            severity: self.severity,
+            code: self.code.map(View),
+            status: self.status,
+            kind: self.kind,
+            #[cfg(feature = "error-ids")]
+            id: crate::ErrorId::generate(),
+        };
+        #[cfg(feature = "std")]
+        crate::call_creation_hook(error.message.as_str(), error.location);
+        error
+    }
+}
+
+pub(crate) fn severity(error: &(dyn error::Error + 'static)) -> Option<crate::Severity> {
+    error.downcast_ref::<FormattedError>()?.severity
+}
+
+pub(crate) fn error_code(error: &(dyn error::Error + 'static)) -> Option<crate::ErrorCode> {
+    Some(error.downcast_ref::<FormattedError>()?.code.as_ref()?.0)
+}
+
+pub(crate) fn http_status(error: &(dyn error::Error + 'static)) -> Option<u16> {
+    error.downcast_ref::<FormattedError>()?.status
+}
+
+#[cfg(feature = "error-ids")]
+pub(crate) fn error_id(error: &(dyn error::Error + 'static)) -> Option<crate::ErrorId> {
+    if let Some(error) = error.downcast_ref::<FormattedWrapError>() {
+        return Some(error.id);
+    }
+    Some(error.downcast_ref::<FormattedError>()?.id)
+}
+
+pub(crate) fn kind(error: &(dyn error::Error + 'static)) -> Option<crate::Kind> {
+    if let Some(error) = error.downcast_ref::<FormattedWrapError>() {
+        return error.kind;
+    }
+    if let Some(error) = error.downcast_ref::<KindError>() {
+        return Some(error.kind);
+    }
+    error.downcast_ref::<FormattedError>()?.kind
+}
+
+/// Set the kind of an error, without adding a message to the chain
+///
+/// Errors created by this crate are updated in place. Other errors are
+/// wrapped in a transparent `KindError`.
+pub(crate) fn with_kind(error: crate::Error, kind: crate::Kind) -> crate::Error {
+    let error = match error.downcast::<FormattedError>() {
+        Ok(mut error) => {
+            error.kind = Some(kind);
+            return error;
+        }
+        Err(error) => error,
+    };
+    match error.downcast::<FormattedWrapError>() {
+        Ok(mut error) => {
+            error.kind = Some(kind);
+            error
+        }
+        Err(error) => Box::new(KindError { kind, error }),
     }
 }
 
+#[derive(Debug)]
+struct KindError {
+    kind: crate::Kind,
+    error: crate::Error,
+}
+
+impl fmt::Display for KindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl error::Error for KindError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.error.source()
+    }
+
+    #[allow(deprecated)]
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.error.cause()
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.error.provide(request);
+    }
+}
+
+/// The type name of the source of an error created by `wrap!`
+#[derive(Debug)]
+pub(crate) struct SourceType(pub(crate) fn() -> &'static str);
+
 #[derive(Debug)]
 struct FormattedWrapError {
-    message: borrow::Cow<'static, str>,
-    source: crate::Error,
+    message: Message,
+    source: Option<crate::Error>,
+    source_type: View<SourceType>,
+    location: &'static panic::Location<'static>,
+    fields: Vec<crate::Field>,
+    kind: Option<crate::Kind>,
+    #[cfg(feature = "error-ids")]
+    id: crate::ErrorId,
 }
 
 impl fmt::Display for FormattedWrapError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        write!(f, "{}", self.message)?;
+        if f.alternate() {
+            if let Some((first, rest)) = self.fields.split_first() {
+                write!(f, " ({}", first)?;
+                for field in rest {
+                    write!(f, ", {}", field)?;
+                }
+                write!(f, ")")?;
+            }
+        }
+        write_location(f, self.location)
     }
 }
 
 impl error::Error for FormattedWrapError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        Some(self.source.as_ref())
+        self.source.as_ref().map(|source| source.as_ref() as _)
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.source.as_ref().map(|_| &self.source_type as _)
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        request.provide_ref::<panic::Location<'static>>(self.location);
+        if let Some(source) = &self.source {
+            source.provide(request);
+        }
     }
 }
 
 #[inline]
 #[must_use]
-pub fn wrap_error_from_args(
-    source: impl Into<crate::Error>,
+#[track_caller]
+pub fn wrap_error_from_args<E: Into<crate::Error>>(
+    source: E,
     args: fmt::Arguments<'_>,
 ) -> impl error::Error + Send + Sync + 'static {
-    let message = if let Some(message) = args.as_str() {
-        borrow::Cow::Borrowed(message)
-    } else {
-        borrow::Cow::Owned(fmt::format(args))
+    wrap(Source::new(source), args, Vec::new(), None)
+}
+
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn wrap_source_from_args(
+    source: Source,
+    args: fmt::Arguments<'_>,
+) -> impl error::Error + Send + Sync + 'static {
+    wrap(source, args, Vec::new(), None)
+}
+
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn wrap_error_with_fields(
+    source: Source,
+    args: fmt::Arguments<'_>,
+    fields: impl Into<Vec<crate::Field>>,
+) -> impl error::Error + Send + Sync + 'static {
+    wrap(source, args, fields.into(), None)
+}
+
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn wrap_error_with_kind<const N: usize>(
+    kind: crate::Kind,
+    source: Source,
+    args: fmt::Arguments<'_>,
+    fields: [crate::Field; N],
+) -> impl error::Error + Send + Sync + 'static {
+    wrap(source, args, fields.into(), Some(kind))
+}
+
+/// Create an error from a message, without formatting or copying it
+#[track_caller]
+pub(crate) fn error_from_cow(message: Cow<'static, str>) -> crate::Error {
+    Box::new(ErrorBuilder::new().build_message(cow_message(message)))
+}
+
+/// Wrap an error with a message, without formatting or copying it
+#[track_caller]
+pub(crate) fn wrap_error_from_cow<E: Into<crate::Error>>(
+    source: E,
+    message: Cow<'static, str>,
+) -> crate::Error {
+    let source = Source::new(source);
+    let location = panic::Location::caller();
+    Box::new(wrap_message(
+        source,
+        cow_message(message),
+        Vec::new(),
+        None,
+        location,
+    ))
+}
+
+/// Wrap an error that has been created elsewhere, e.g. in a future that is
+/// polled later
+#[cfg(any(feature = "async", feature = "std"))]
+pub(crate) fn wrap_error_at<E: Into<crate::Error>>(
+    source: E,
+    args: fmt::Arguments<'_>,
+    location: &'static panic::Location<'static>,
+) -> crate::Error {
+    Box::new(wrap_at(
+        Source::new(source),
+        args,
+        Vec::new(),
+        None,
+        location,
+    ))
+}
+
+#[track_caller]
+fn wrap(
+    source: Source,
+    args: fmt::Arguments<'_>,
+    fields: Vec<crate::Field>,
+    kind: Option<crate::Kind>,
+) -> FormattedWrapError {
+    wrap_at(source, args, fields, kind, panic::Location::caller())
+}
+
+fn wrap_at(
+    source: Source,
+    args: fmt::Arguments<'_>,
+    fields: Vec<crate::Field>,
+    kind: Option<crate::Kind>,
+    location: &'static panic::Location<'static>,
+) -> FormattedWrapError {
+    wrap_message(source, format_message(args), fields, kind, location)
+}
+
+fn wrap_message(
+    source: Source,
+    message: Message,
+    fields: Vec<crate::Field>,
+    kind: Option<crate::Kind>,
+    location: &'static panic::Location<'static>,
+) -> FormattedWrapError {
+    // wrapping keeps the ID of the source, so that every layer of a chain
+    // can be found with the same ID
+    let Source {
+        error: source,
+        type_name,
+    } = source;
+    #[cfg(feature = "error-ids")]
+    let id = source
+        .as_ref()
+        .and_then(|source| crate::error_id(source.as_ref()))
+        .unwrap_or_else(crate::ErrorId::generate);
+    let error = FormattedWrapError {
+        source,
+        source_type: View(SourceType(type_name)),
+        message,
+        location,
+        fields,
+        kind,
+        #[cfg(feature = "error-ids")]
+        id,
     };
-    FormattedWrapError {
+    #[cfg(feature = "std")]
+    {
+        crate::call_creation_hook(error.message.as_str(), error.location);
+        crate::reporter::wrapped(&error);
+    }
+    error
+}
+
+type LazyMessage = Box<dyn Fn(&mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync>;
+
+struct LazyWrapError {
+    message: LazyMessage,
+    source: crate::Error,
+    location: &'static panic::Location<'static>,
+}
+
+impl fmt::Debug for LazyWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Message<'a>(&'a LazyMessage);
+
+        impl fmt::Debug for Message<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_char('"')?;
+                (self.0)(f)?;
+                f.write_char('"')
+            }
+        }
+
+        f.debug_struct("LazyWrapError")
+            .field("message", &Message(&self.message))
+            .field("source", &self.source)
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl fmt::Display for LazyWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.message)(f)?;
+        write_location(f, self.location)
+    }
+}
+
+impl error::Error for LazyWrapError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        request.provide_ref::<panic::Location<'static>>(self.location);
+        self.source.provide(request);
+    }
+}
+
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn lazy_wrap_error<F, D>(
+    source: impl Into<crate::Error>,
+    message: F,
+) -> impl error::Error + Send + Sync + 'static
+where
+    F: Fn() -> D + Send + Sync + 'static,
+    D: fmt::Display,
+{
+    LazyWrapError {
+        message: Box::new(move |f| write!(f, "{}", message())),
         source: source.into(),
-        message,
+        location: panic::Location::caller(),
+    }
+}
+
+pub fn field_display(name: &'static str, value: &impl fmt::Display) -> crate::Field {
+    crate::Field {
+        name,
+        value: value.to_string(),
+    }
+}
+
+pub fn field_debug(name: &'static str, value: &impl fmt::Debug) -> crate::Field {
+    crate::Field {
+        name,
+        value: format!("{:?}", value),
+    }
+}
+
+pub(crate) fn fields<'a>(error: &'a (dyn error::Error + 'static)) -> &'a [crate::Field] {
+    match error.downcast_ref::<FormattedWrapError>() {
+        Some(error) => &error.fields,
+        None => &[],
+    }
+}
+
+#[derive(Debug)]
+struct LocalFormattedWrapError {
+    message: Message,
+    source: crate::LocalError,
+    location: &'static panic::Location<'static>,
+}
+
+impl fmt::Display for LocalFormattedWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        write_location(f, self.location)
+    }
+}
+
+impl error::Error for LocalFormattedWrapError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        request.provide_ref::<panic::Location<'static>>(self.location);
+        self.source.provide(request);
+    }
+}
+
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn local_wrap_error_from_args(
+    source: impl Into<crate::LocalError>,
+    args: fmt::Arguments<'_>,
+) -> impl error::Error + 'static {
+    LocalFormattedWrapError {
+        message: format_message(args),
+        source: source.into(),
+        location: panic::Location::caller(),
+    }
+}
+
+// Messages without any interpolation, e.g. `err!("fixed message")`, are
+// `&'static str` literals, so they can be stored without allocating. Most
+// other messages are short enough to be stored inline.
+fn format_message(args: fmt::Arguments<'_>) -> Message {
+    if let Some(message) = args.as_str() {
+        return Message::Static(message);
+    }
+    let mut message = Message::Inline {
+        len: 0,
+        bytes: [0; INLINE_CAPACITY],
+    };
+    fmt::write(&mut message, args)
+        .expect("a Display implementation returned an error unexpectedly");
+    message
+}
+
+// Owned messages are kept in their allocation, even if they would fit inline
+fn cow_message(message: Cow<'static, str>) -> Message {
+    match message {
+        Cow::Borrowed(message) => Message::Static(message),
+        Cow::Owned(message) => Message::Heap(message),
+    }
+}
+
+const INLINE_CAPACITY: usize = 22;
+
+/// The message of an error created with `err!` or `wrap!`
+enum Message {
+    Static(&'static str),
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(String),
+}
+
+impl Message {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Static(message) => message,
+            Self::Inline { len, bytes } => core::str::from_utf8(&bytes[..usize::from(*len)])
+                .expect("only complete strings are written to inline messages"),
+            Self::Heap(message) => message,
+        }
     }
 }
 
-pub(crate) struct WrapError {
-    pub(crate) inner: crate::Error,
+impl fmt::Write for Message {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self {
+            Self::Inline { len, bytes } => {
+                let start = usize::from(*len);
+                match bytes.get_mut(start..start + s.len()) {
+                    Some(dest) => {
+                        dest.copy_from_slice(s.as_bytes());
+                        *len += s.len() as u8;
+                    }
+                    None => {
+                        // leave room for the rest of the message, to avoid
+                        // reallocating on every write
+                        let mut message = String::with_capacity(2 * (start + s.len()));
+                        message.push_str(self.as_str());
+                        message.push_str(s);
+                        *self = Self::Heap(message);
+                    }
+                }
+            }
+            Self::Heap(message) => message.push_str(s),
+            Self::Static(_) => unreachable!("static messages are never written to"),
+        }
+        Ok(())
+    }
 }
 
-impl fmt::Debug for WrapError {
+impl fmt::Debug for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.inner.fmt(f)
+        fmt::Debug::fmt(self.as_str(), f)
     }
 }
 
-impl fmt::Display for WrapError {
+impl fmt::Display for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.inner.fmt(f)
+        f.write_str(self.as_str())
     }
 }
 
-impl error::Error for WrapError {
+pub(crate) fn location(
+    error: &(dyn error::Error + 'static),
+) -> Option<&'static panic::Location<'static>> {
+    if let Some(error) = error.downcast_ref::<FormattedError>() {
+        Some(error.location)
+    } else if let Some(error) = error.downcast_ref::<FormattedWrapError>() {
+        Some(error.location)
+    } else if let Some(error) = error.downcast_ref::<LazyWrapError>() {
+        Some(error.location)
+    } else if let Some(error) = error.downcast_ref::<LocalFormattedWrapError>() {
+        Some(error.location)
+    } else {
+        None
+    }
+}
+
+/// One layer of a chain whose types are hidden, see `ees::opaque`
+///
+/// Every layer shares the original error, and points to the error of its
+/// layer in the chain, so that the layers don't need to borrow from each
+/// other. The view of the error isn't forwarded, so that its type is hidden
+/// as well.
+pub(crate) struct OpaqueError {
+    // owns the error that `layer` points to
+    _chain: alloc::sync::Arc<crate::Error>,
+    layer: *const (dyn error::Error + 'static),
+    source: Option<Box<OpaqueError>>,
+}
+
+// SAFETY: `layer` is only used to get a shared reference to an error in the
+// chain, which is `Send` and `Sync`.
+unsafe impl Send for OpaqueError {}
+unsafe impl Sync for OpaqueError {}
+
+impl OpaqueError {
+    pub(crate) fn new(error: crate::Error) -> Self {
+        let chain = alloc::sync::Arc::new(error);
+        let layers: Vec<_> = crate::chain::static_chain(chain.as_ref().as_ref())
+            .map(|layer| layer as *const _)
+            .collect();
+        let mut opaque = None;
+        for layer in layers.into_iter().rev() {
+            opaque = Some(Self {
+                _chain: alloc::sync::Arc::clone(&chain),
+                layer,
+                source: opaque.map(Box::new),
+            });
+        }
+        opaque.expect("an error chain is never empty")
+    }
+
+    fn error(&self) -> &dyn error::Error {
+        // SAFETY: `layer` borrows from the error in `_chain`, which is kept
+        // alive, never moved and never mutated by `self`.
+        unsafe { &*self.layer }
+    }
+}
+
+impl fmt::Debug for OpaqueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.error(), f)
+    }
+}
+
+impl fmt::Display for OpaqueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.error(), f)
+    }
+}
+
+impl error::Error for OpaqueError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        self.inner.source()
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
+pub(crate) struct DisplayError<D>(pub(crate) D);
+
+impl<D: fmt::Display> fmt::Debug for DisplayError<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0.to_string())
+    }
+}
+
+impl<D: fmt::Display> fmt::Display for DisplayError<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<D: fmt::Display> error::Error for DisplayError<D> {}
+
+#[cfg(feature = "std")]
+pub fn report_main<T: process::Termination, E: Into<crate::MainError>>(
+    result: Result<T, E>,
+) -> process::ExitCode {
+    match result {
+        Ok(value) => value.report(),
+        Err(error) => process::Termination::report(error.into()),
+    }
+}
+
+#[cfg(feature = "std")]
+#[inline]
+#[must_use]
+#[track_caller]
+pub fn coded_error(
+    code: &'static str,
+    args: &[(&str, &dyn fmt::Display)],
+) -> impl error::Error + Send + Sync + 'static {
+    let message = crate::catalog::format_message(code, args);
+    ErrorBuilder::new()
+        .code(code)
+        .build(format_args!("{}", message))
+}
+
+#[cfg(feature = "std")]
+pub fn breadcrumb(args: fmt::Arguments<'_>) {
+    crate::breadcrumbs::record(args);
+}
+
+#[cfg(feature = "std")]
+pub fn exit_with_error(code: i32, error: impl Into<crate::MainError>) -> ! {
+    error.into().print();
+    process::exit(code)
+}
+
+/// Write the `Display` output of `value` as a JSON string literal
+pub(crate) fn write_json_string(f: &mut dyn fmt::Write, value: &dyn fmt::Display) -> fmt::Result {
+    struct Escape<'a>(&'a mut dyn fmt::Write);
+
+    impl fmt::Write for Escape<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for c in s.chars() {
+                match c {
+                    '"' => self.0.write_str("\\\"")?,
+                    '\\' => self.0.write_str("\\\\")?,
+                    '\n' => self.0.write_str("\\n")?,
+                    '\r' => self.0.write_str("\\r")?,
+                    '\t' => self.0.write_str("\\t")?,
+                    c if c.is_control() => write!(self.0, "\\u{:04x}", c as u32)?,
+                    c => self.0.write_char(c)?,
+                }
+            }
+            Ok(())
+        }
+    }
+
+    f.write_char('"')?;
+    write!(&mut Escape(f), "{}", value)?;
+    f.write_char('"')
+}
+
+pub struct SourceWrap<'a, T: ?Sized>(pub &'a T);
+
+impl<T: ?Sized> Clone for SourceWrap<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for SourceWrap<'_, T> {}
+
+// These traits select how a field is turned into a source using autoref-based
+// specialization: `BoxedSource` is implemented for references, and so takes
+// precedence over `ErrorSource` when both apply.
+pub trait ErrorSource<'a> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)>;
+}
+
+impl<'a, E: error::Error + 'static> ErrorSource<'a> for SourceWrap<'a, E> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)> {
+        Some(self.0)
+    }
+}
+
+pub trait BoxedSource<'a> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)>;
+}
+
+impl<'a> BoxedSource<'a> for &SourceWrap<'a, crate::Error> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+impl<'a> BoxedSource<'a> for &SourceWrap<'a, crate::LocalError> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+impl<'a, E: error::Error + 'static> BoxedSource<'a> for &SourceWrap<'a, Option<E>> {
+    fn ees_source(self) -> Option<&'a (dyn error::Error + 'static)> {
+        self.0.as_ref().map(|error| error as _)
+    }
+}
+
+pub fn chain_messages(error: Option<&(dyn error::Error + 'static)>) -> Vec<alloc::string::String> {
+    error.map_or_else(Vec::new, crate::chain_messages)
+}
+
+/// Returns the printed chain if it doesn't contain an error of type `E`
+pub fn chain_without<E: error::Error + 'static>(
+    error: Option<&(dyn error::Error + 'static)>,
+) -> Option<alloc::string::String> {
+    let error = match error {
+        Some(error) => error,
+        None => return Some(alloc::string::String::new()),
+    };
+    if crate::chain::static_chain(error).any(|error| error.is::<E>()) {
+        None
+    } else {
+        Some(crate::print_error_chain(error).to_string())
+    }
+}
+
+/// Converts the tuple of options built by `try_all!` into an option of a tuple
+pub trait AllSome {
+    type Output;
+
+    fn all_some(self) -> Option<Self::Output>;
+}
+
+macro_rules! impl_all_some {
+    ($($name:ident)+) => {
+        impl<$($name),+> AllSome for ($(Option<$name>,)+) {
+            type Output = ($($name,)+);
+
+            #[allow(non_snake_case)]
+            fn all_some(self) -> Option<Self::Output> {
+                let ($($name,)+) = self;
+                Some(($($name?,)+))
+            }
+        }
+    };
+}
+
+impl_all_some!(A);
+impl_all_some!(A B);
+impl_all_some!(A B C);
+impl_all_some!(A B C D);
+impl_all_some!(A B C D E);
+impl_all_some!(A B C D E F);
+impl_all_some!(A B C D E F G);
+impl_all_some!(A B C D E F G H);
+
+/// The source of `wrap!`, which is either an error or an `Option` of an error
+pub struct WrapSource<T>(pub Option<T>);
+
+/// The source of a wrapped error, together with the name of its type before it
+/// was boxed
+pub struct Source {
+    error: Option<crate::Error>,
+    type_name: fn() -> &'static str,
+}
+
+impl Source {
+    fn new<E: Into<crate::Error>>(error: E) -> Self {
+        Self::optional(Some(error))
+    }
+
+    fn optional<E: Into<crate::Error>>(error: Option<E>) -> Self {
+        Self {
+            error: error.map(Into::into),
+            type_name: any::type_name::<E>,
+        }
+    }
+}
+
+// As with `SourceWrap`, `OptionalSource` is implemented for a reference to
+// `&mut WrapSource`, and so takes precedence over `IntoSource` for options.
+pub trait IntoSource {
+    fn ees_into_source(&mut self) -> Source;
+}
+
+impl<E: Into<crate::Error>> IntoSource for WrapSource<E> {
+    fn ees_into_source(&mut self) -> Source {
+        Source::optional(self.0.take())
+    }
+}
+
+pub trait OptionalSource {
+    fn ees_into_source(&mut self) -> Source;
+}
+
+impl<E: Into<crate::Error>> OptionalSource for &mut WrapSource<Option<E>> {
+    fn ees_into_source(&mut self) -> Source {
+        Source::optional(self.0.take().flatten())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(error: crate::Error) -> Message {
+        let error = error.downcast::<FormattedError>();
+        error.expect("expected a FormattedError").message
+    }
+
+    #[test]
+    fn literal_messages_are_borrowed() {
+        let literal = message(crate::err!("fixed message").into());
+        assert!(matches!(literal, Message::Static("fixed message")));
+    }
+
+    #[test]
+    fn short_messages_are_inline() {
+        let short = message(crate::err!("code {}", 42 + 1).into());
+        assert!(matches!(short, Message::Inline { .. }));
+        assert_eq!(short.as_str(), "code 43");
+        let exact = message(crate::err!("{}", "x".repeat(INLINE_CAPACITY)).into());
+        assert!(matches!(exact, Message::Inline { .. }));
+        let long = message(crate::err!("{} and {}", "x".repeat(20), "y".repeat(20)).into());
+        assert!(matches!(long, Message::Heap(_)));
+        assert_eq!(
+            long.as_str(),
+            format!("{} and {}", "x".repeat(20), "y".repeat(20))
+        );
+    }
+
+    #[test]
+    fn wrapping_reuses_the_box() {
+        let error: crate::Error = crate::err!("root").into();
+        let address = &*error as *const dyn error::Error as *const ();
+        let wrapped = crate::wrap!(error, "outer");
+        let source = error::Error::source(&wrapped).expect("wrap! sets a source");
+        assert!(core::ptr::eq(
+            source as *const dyn error::Error as *const (),
+            address
+        ));
     }
 }
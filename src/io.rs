@@ -0,0 +1,263 @@
+//! Readers and writers that add a label, e.g. a path or an address, to their
+//! errors
+//!
+//! The label is added to every error of the inner reader or writer, including
+//! errors that are returned to third-party code consuming it, which usually
+//! can't add context itself:
+//!
+//! ```
+//! use std::io::Read;
+//!
+//! let input: &[u8] = b"12";
+//! let mut reader = ees::io::ReadCtx::new(input, "stdin");
+//! let e = reader.read_exact(&mut [0; 4]).unwrap_err();
+//! assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+//! assert_eq!(
+//!     ees::print_error_chain(&e).to_string(),
+//!     "stdin: failed to fill whole buffer"
+//! );
+//! ```
+//!
+//! The errors remain [io::Error]s with the kind of the original error, which
+//! is their source. `Interrupted` errors are returned unchanged, because they
+//! are retried rather than reported.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Seek, Write},
+    panic,
+};
+
+/// Add the label to an error, keeping its kind
+fn label_error(
+    error: io::Error,
+    label: &str,
+    location: &'static panic::Location<'static>,
+) -> io::Error {
+    if error.kind() == io::ErrorKind::Interrupted {
+        return error;
+    }
+    let kind = error.kind();
+    let error = crate::internal::wrap_error_at(error, format_args!("{}", label), location);
+    io::Error::new(kind, error)
+}
+
+/// A reader that adds a label to its errors, see the [module documentation](self)
+#[derive(Debug)]
+pub struct ReadCtx<R> {
+    inner: R,
+    label: String,
+    location: &'static panic::Location<'static>,
+}
+
+impl<R> ReadCtx<R> {
+    /// Wrap a reader
+    ///
+    /// The location of this call becomes the location of the errors.
+    #[track_caller]
+    pub fn new(inner: R, label: impl fmt::Display) -> Self {
+        Self {
+            inner,
+            label: label.to_string(),
+            location: panic::Location::caller(),
+        }
+    }
+
+    /// The label that is added to errors
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The inner reader
+    #[must_use]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The inner reader
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwrap the inner reader
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn with_label<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        result.map_err(|error| label_error(error, &self.label, self.location))
+    }
+}
+
+impl<R: Read> Read for ReadCtx<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        self.with_label(result)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        let result = self.inner.read_vectored(bufs);
+        self.with_label(result)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let result = self.inner.read_to_end(buf);
+        self.with_label(result)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        let result = self.inner.read_to_string(buf);
+        self.with_label(result)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let result = self.inner.read_exact(buf);
+        self.with_label(result)
+    }
+}
+
+impl<R: BufRead> BufRead for ReadCtx<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let (label, location) = (&self.label, self.location);
+        self.inner
+            .fill_buf()
+            .map_err(|error| label_error(error, label, location))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+    }
+}
+
+impl<R: Seek> Seek for ReadCtx<R> {
+    fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64> {
+        let result = self.inner.seek(position);
+        self.with_label(result)
+    }
+}
+
+/// A writer that adds a label to its errors, see the [module documentation](self)
+#[derive(Debug)]
+pub struct WriteCtx<W> {
+    inner: W,
+    label: String,
+    location: &'static panic::Location<'static>,
+}
+
+impl<W> WriteCtx<W> {
+    /// Wrap a writer
+    ///
+    /// The location of this call becomes the location of the errors.
+    #[track_caller]
+    pub fn new(inner: W, label: impl fmt::Display) -> Self {
+        Self {
+            inner,
+            label: label.to_string(),
+            location: panic::Location::caller(),
+        }
+    }
+
+    /// The label that is added to errors
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The inner writer
+    #[must_use]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The inner writer
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwrap the inner writer
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn with_label<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        result.map_err(|error| label_error(error, &self.label, self.location))
+    }
+}
+
+impl<W: Write> Write for WriteCtx<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.with_label(result)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let result = self.inner.write_vectored(bufs);
+        self.with_label(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.inner.flush();
+        self.with_label(result)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let result = self.inner.write_all(buf);
+        self.with_label(result)
+    }
+}
+
+impl<W: Seek> Seek for WriteCtx<W> {
+    fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64> {
+        let result = self.inner.seek(position);
+        self.with_label(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufRead, Read, Write};
+
+    #[test]
+    fn read_write() {
+        let (mut reader, line) = (super::ReadCtx::new(&b"a\nb"[..], "input"), line!());
+        let mut first = String::new();
+        reader.read_line(&mut first).unwrap();
+        assert_eq!(first, "a\n");
+        let e = reader.read_exact(&mut [0; 2]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        let e = e.into_inner().unwrap();
+        assert_eq!(e.to_string(), "input");
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+
+        let mut buffer = [0; 2];
+        let mut writer = super::WriteCtx::new(&mut buffer[..], "10.0.0.1:80");
+        let e = writer.write_all(b"abc").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(
+            crate::print_error_chain(&e).to_string(),
+            "10.0.0.1:80: failed to write whole buffer"
+        );
+        assert_eq!(writer.label(), "10.0.0.1:80");
+    }
+
+    #[test]
+    fn interrupted() {
+        struct Interrupted;
+
+        impl Read for Interrupted {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::Interrupted.into())
+            }
+        }
+
+        let e = super::ReadCtx::new(Interrupted, "input")
+            .read(&mut [0; 1])
+            .unwrap_err();
+        assert!(e.get_ref().is_none());
+    }
+}
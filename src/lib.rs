@@ -25,21 +25,275 @@
 //!     println!("Error: {}", ees::print_error_chain(error));
 //! }
 //!
-//! // Use ees::MainResult to automatically create nicely-
-//! // formatted error messages in the main() function
-//! fn main() -> ees::MainResult {
-//!     do_work()?;
-//!     do_work().map_err(
-//!         // add additional context
-//!         |e| ees::wrap!(e, "failed to do work"))?;
-//!     Ok(())
+//! fn main() {
+//!     // add additional context
+//!     if let Err(e) = do_work().map_err(|e| ees::wrap!(e, "failed to do work")) {
+//!         take_an_error(&e);
+//!     }
+//! }
+//! ```
+//!
+//! With the default `std` feature, returning a [MainResult] from `main()`
+//! prints the error chain in a report instead.
+//!
+//! ## Interoperability with `anyhow`
+//!
+//! `anyhow::Error` converts into [Error] while keeping every layer of its cause
+//! chain, so `?` works in functions returning [Result], and [print_error_chain]
+//! renders the same chain that `anyhow` would. In the other direction, [to_err]
+//! turns an [Error] into a type that `anyhow` accepts:
+//!
+//! ```ignore
+//! fn from_anyhow(e: anyhow::Error) -> ees::Error {
+//!     e.into()
+//! }
+//!
+//! fn to_anyhow(e: ees::Error) -> anyhow::Error {
+//!     anyhow::Error::new(ees::to_err(e))
+//! }
+//! ```
+//!
+//! ## Interoperability with `eyre`
+//!
+//! The same applies to `eyre::Report`, so libraries using this crate can be
+//! consumed from `eyre`-based applications without losing context:
+//!
+//! ```ignore
+//! fn from_eyre(report: eyre::Report) -> ees::Error {
+//!     report.into()
+//! }
+//!
+//! fn to_eyre(e: ees::Error) -> eyre::Report {
+//!     eyre::Report::new(ees::to_err(e))
+//! }
+//! ```
+//!
+//! ## Interoperability with PyO3
+//!
+//! Extension modules can keep the error chain when raising Python exceptions,
+//! with each cause becoming the `__cause__` of the exception above it. The
+//! orphan rule requires a local error type for the conversion into `PyErr`:
+//!
+//! ```ignore
+//! use pyo3::{exceptions::PyRuntimeError, prelude::*};
+//!
+//! struct ModuleError(ees::Error);
+//!
+//! impl<E: Into<ees::Error>> From<E> for ModuleError {
+//!     fn from(error: E) -> Self {
+//!         Self(error.into())
+//!     }
+//! }
+//!
+//! impl From<ModuleError> for PyErr {
+//!     fn from(error: ModuleError) -> Self {
+//!         Python::with_gil(|py| {
+//!             // start with the root cause, so that each exception can be
+//!             // the cause of the next one
+//!             let mut exception = None;
+//!             for message in ees::chain_messages(error.0.as_ref()).into_iter().rev() {
+//!                 let outer = PyRuntimeError::new_err(message);
+//!                 outer.set_cause(py, exception);
+//!                 exception = Some(outer);
+//!             }
+//!             exception.expect("the chain contains at least one error")
+//!         })
+//!     }
+//! }
+//! ```
+//!
+//! In the other direction, [DeserializedError] rebuilds the chain from the
+//! messages of an exception and its causes:
+//!
+//! ```ignore
+//! fn from_py_err(error: PyErr) -> ees::Error {
+//!     Python::with_gil(|py| {
+//!         let mut messages = Vec::new();
+//!         let mut error = Some(error);
+//!         while let Some(exception) = error {
+//!             messages.push(exception.value(py).to_string());
+//!             error = exception.cause(py);
+//!         }
+//!         ees::DeserializedError::from_messages(messages)
+//!             .expect("there is at least one exception")
+//!             .into()
+//!     })
 //! }
 //! ```
+//!
+//! ## `no_std` support
+//!
+//! The default `std` feature can be disabled to use this crate in `no_std`
+//! environments that provide `alloc`. Everything except [MainError],
+//! [MainResult], [run], [Reporter], [ExitMap], [Redactor], [Translations],
+//! crash reports (see [set_crash_report_path]), [breadcrumb!], the panic hook,
+//! [retry()], the [env](mod@env), [ffi], [fs], [io] and [os] modules and the
+//! `color`, `diagnostics`, `error-ids`, `macros`, `sentry` and `timestamps`
+//! features remains available, and is based on [core::error::Error].
+//!
+//! ## Generic member access
+//!
+//! On nightly toolchains, building with `RUSTFLAGS="--cfg ees_nightly"`
+//! enables support for `Error::provide`. Errors created with `err!` and
+//! `wrap!` provide their [Location](core::panic::Location), and wrapper types
+//! forward `provide` to their sources, so values provided by a source can
+//! still be requested from the wrapped error.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(ees_nightly, feature(error_generic_member_access))]
+
+extern crate alloc;
 
 #[doc(hidden)]
 pub mod internal;
 
-use std::{error, fmt};
+/// Wrap an ordinary `fn main() -> ees::Result<()>`, printing the error chain
+/// and selecting the exit code if it fails
+///
+/// ```no_run
+/// #[ees::main]
+/// fn main() -> ees::Result<()> {
+///     std::fs::File::open("hello world")?;
+///     Ok(())
+/// }
+/// ```
+///
+/// `async fn main()` is supported by naming a runtime, either
+/// `#[ees::main(tokio)]` or `#[ees::main(async_std)]`.
+#[cfg(feature = "macros")]
+pub use ees_macros::main;
+
+/// Derive `Display` and `Error` for a struct or enum
+///
+/// ```
+/// #[derive(Debug, ees::Derive)]
+/// enum ConfigError {
+///     #[error("failed to read {path}")]
+///     Read {
+///         path: String,
+///         #[source]
+///         cause: std::io::Error,
+///     },
+///     #[error("invalid port {0}")]
+///     InvalidPort(u32),
+///     #[error(transparent)]
+///     Other(#[from] ees::Error),
+/// }
+///
+/// let e: ees::Error = ees::wrap!(ees::err!("root"), "outer").into();
+/// let e = ConfigError::from(e);
+/// assert_eq!(ees::print_error_chain(e).to_string(), "outer: root");
+/// ```
+///
+/// The message of each struct or enum variant is given with
+/// `#[error("...")]`, where fields can be referred to by name (`{path}`) or by
+/// index (`{0}`). `#[error(transparent)]` forwards both the message and the
+/// source to the only field.
+///
+/// The field marked with `#[source]`, or otherwise the field named `source`,
+/// is returned by [Error::source](std::error::Error::source). This can be any
+/// error type, an [Error], or an `Option` of a concrete error type. `#[from]` additionally
+/// generates a `From` impl.
+#[cfg(feature = "derive")]
+pub use ees_macros::Derive;
+
+#[cfg(feature = "std")]
+mod breadcrumbs;
+#[cfg(feature = "std")]
+mod catalog;
+mod chain;
+#[cfg(feature = "std")]
+mod command;
+mod composite;
+#[cfg(feature = "std")]
+mod crash_report;
+#[cfg(feature = "std")]
+pub mod env;
+#[cfg(feature = "std")]
+mod exit_map;
+mod ext;
+#[cfg(feature = "std")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod fs;
+#[cfg(feature = "async")]
+mod future;
+mod http;
+#[cfg(feature = "std")]
+pub mod io;
+mod list;
+#[cfg(feature = "std")]
+pub mod os;
+#[cfg(feature = "std")]
+mod panic_hook;
+mod parse;
+mod redact;
+mod report;
+#[cfg(feature = "std")]
+mod reporter;
+#[cfg(feature = "std")]
+mod retry;
+#[cfg(feature = "sentry")]
+mod sentry;
+mod shared;
+#[cfg(feature = "fancy")]
+mod snippet;
+#[cfg(feature = "test-util")]
+mod test_util;
+#[cfg(feature = "std")]
+mod translate;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use breadcrumbs::breadcrumbs;
+#[cfg(feature = "std")]
+pub use catalog::{catalog_entry, register_catalog, CatalogEntry};
+pub use chain::{Chain, ChainFormat, ErrorChain, FormattedChain};
+#[cfg(feature = "std")]
+pub use command::{check_output, check_status};
+pub use composite::{CompositeError, Deferred};
+#[cfg(feature = "std")]
+pub use crash_report::set_crash_report_path;
+#[cfg(feature = "std")]
+pub use exit_map::{set_exit_map, ExitMap};
+#[cfg(feature = "std")]
+pub use ext::PathResultExt;
+pub use ext::{ErrorExt, ResultExt};
+#[cfg(feature = "async")]
+pub use future::{FutureExt, WrapErr, WrapErrWith};
+pub use http::{http_status, HttpError};
+pub use list::{join_errors, ErrorCollector, ErrorList, ErrorListIter, ResultIteratorExt};
+#[cfg(feature = "std")]
+pub use panic_hook::{catch_panic, install_panic_hook};
+pub use parse::{convert_ctx, parse, parse_ctx};
+#[cfg(feature = "std")]
+pub use redact::{add_redactor, Redactor};
+pub use redact::{redact, Redacted};
+pub use report::Report;
+#[cfg(feature = "std")]
+pub use reporter::{set_reporter, Reporter};
+#[cfg(feature = "std")]
+pub use retry::{is_transient, retry, RetryPolicy};
+#[cfg(feature = "sentry")]
+pub use sentry::SentryReporter;
+pub use shared::SharedError;
+#[cfg(feature = "fancy")]
+pub use snippet::Snippet;
+#[cfg(feature = "test-util")]
+pub use test_util::{TestError, TestErrorProbe};
+#[cfg(feature = "std")]
+pub use translate::{set_translations, Translations};
+
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{error, fmt, panic};
+#[cfg(feature = "std")]
+use std::process;
 
 /// Represents an arbitrary owned error
 pub type Error = Box<dyn error::Error + Send + Sync + 'static>;
@@ -47,124 +301,2427 @@ pub type Error = Box<dyn error::Error + Send + Sync + 'static>;
 /// Represents an arbitrary borrowed error with a given lifetime
 pub type ErrorRef<'a> = &'a (dyn error::Error + 'static);
 
+/// Represents an arbitrary borrowed error that may itself borrow data, e.g.
+/// from the input of a zero-copy parser
+///
+/// Only the outermost error can borrow data, because sources are always
+/// `'static`. Unlike an [ErrorRef], such errors can't be downcast, so helpers
+/// that inspect the types in the chain (e.g. [find_cause]) require an
+/// [ErrorRef]. [print_error_chain], [ChainFormat], [chain], [root_cause],
+/// [chain_messages] and [chain_eq] accept these errors too.
+pub type BorrowedErrorRef<'a> = &'a (dyn error::Error + 'a);
+
 /// `Result<T, Error>`
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Represents an arbitrary owned error that is not necessarily `Send` or `Sync`
+///
+/// This is useful for errors that contain e.g. an `Rc`, which can't be
+/// converted into an [Error]. Use [local_wrap!] to add context to these errors,
+/// or [wrap_snapshot!] to wrap them in an [Error] instead.
+pub type LocalError = Box<dyn error::Error + 'static>;
+
+/// `Result<T, LocalError>`
+pub type LocalResult<T> = core::result::Result<T, LocalError>;
+
+/// Print the complete error chain of an error, separated with colons
+///
+/// Use `{:#}` to print each cause on its own line instead, and `{:+}` to
+/// append the source location of errors created with [err!] or [wrap!].
+/// Fields attached with [wrap!] are included in the `{:#}` format.
+///
+/// The error is stored as-is, so printing a borrowed error such as an
+/// [ErrorRef] doesn't allocate, unless a localizer (see [set_localizer]) or a
+/// [Redactor] is registered.
+#[must_use]
+#[inline]
+pub fn print_error_chain<E: error::Error>(error: E) -> ErrorChain<E> {
+    ErrorChain::new(error)
+}
+
+/// Like [print_error_chain], for an owned [Error]
+///
+/// `Box<dyn Error>` itself doesn't implement [Error](error::Error), so
+/// `print_error_chain(&error)` would require `error.as_ref()`.
+///
+/// ```
+/// let e: ees::Error = ees::wrap!(ees::err!("root"), "outer").into();
+/// assert_eq!(ees::print_owned_chain(&e).to_string(), "outer: root");
+/// ```
+#[must_use]
+#[inline]
+pub fn print_owned_chain(error: &Error) -> ErrorChain<ErrorRef<'_>> {
+    ErrorChain::new(error.as_ref())
+}
+
+/// Print the complete error chain of an error as an indented tree, see
+/// [ChainFormat::tree]
+#[must_use]
+#[inline]
+pub fn print_error_tree<E: error::Error>(error: E) -> FormattedChain<E> {
+    ChainFormat::new().tree(true).display(error)
+}
+
+/// Print the complete error chain of an error, using the `Debug` representation
+/// of each error instead of its message, e.g. to see the fields of errors from
+/// other crates
+///
+/// Each error is printed on its own line, as in the `{:#}` format of
+/// [print_error_chain]. Use `{:#}` to pretty-print each error instead.
+/// Errors whose `Debug` representation includes their source, such as those
+/// created with [wrap!], repeat the rest of the chain.
+///
+/// ```
+/// #[derive(Debug)]
+/// struct Timeout {
+///     seconds: u32,
+/// }
+///
+/// impl std::fmt::Display for Timeout {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "timed out after {}s", self.seconds)
+///     }
+/// }
+///
+/// impl std::error::Error for Timeout {}
+///
+/// let e = ees::wrap!(Timeout { seconds: 30 }, "request failed");
+/// let report = ees::debug_error_chain(&e).to_string();
+/// assert!(report.ends_with("\n\nCaused by:\n    Timeout { seconds: 30 }"));
+/// ```
+#[must_use]
+#[inline]
+pub fn debug_error_chain<'a>(error: impl error::Error + 'a) -> impl fmt::Display + 'a {
+    chain::DebugChain(error)
+}
 
-#[derive(Debug)]
-struct ErrorChain<'a> {
-    error: Box<dyn error::Error + 'a>,
+struct JsonChain<E> {
+    error: E,
 }
 
-impl fmt::Display for ErrorChain<'_> {
+impl<E: error::Error> fmt::Display for JsonChain<E> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut error = self.error.as_ref();
-        write!(f, "{}", &error)?;
-        if f.alternate() {
-            if let Some(first_inner) = error.source() {
-                writeln!(f, "\n\nCaused by:")?;
-                if let Some(second_inner) = first_inner.source() {
-                    writeln!(f, "{: >5}: {}", 0, first_inner)?;
-                    write!(f, "{: >5}: {}", 1, second_inner)?;
-                    error = second_inner;
-                    let mut n = 2;
-                    while let Some(inner) = error.source() {
-                        write!(f, "\n{: >5}: {}", n, inner)?;
-                        error = inner;
-                        n += 1;
-                    }
-                } else {
-                    write!(f, "    {}", first_inner)?;
-                }
+        let type_names = chain::TypeNames::new(&self.error);
+        write!(f, "[")?;
+        for (i, error) in Chain::new(&self.error).enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
             }
-        } else {
-            while let Some(inner) = error.source() {
-                write!(f, ": {}", inner)?;
-                error = inner;
+            write!(f, "{{\"message\":")?;
+            internal::write_json_string(f, &redact::Redacting(&error))?;
+            if let Some(type_name) = type_names.of(error) {
+                write!(f, ",\"type_name\":")?;
+                internal::write_json_string(f, &type_name)?;
+            }
+            write!(f, "}}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Print the error chain as a JSON array of `{"message": "..."}` objects, from
+/// the outermost error to the root cause
+///
+/// This is useful for structured logs, where each cause should remain a
+/// separate field rather than being joined into one string. The objects also
+/// have a `"type_name"` if the type of the error is known, which is the case
+/// in the same situations as for [ChainFormat::type_names]:
+///
+/// ```
+/// let e = std::fs::File::open("does-not-exist.toml").unwrap_err();
+/// let e = ees::wrap!(e, "failed to open config");
+/// let json = ees::print_error_chain_json(&e).to_string();
+/// assert!(json.starts_with(r#"[{"message":"failed to open config"},{"message":"#));
+/// assert!(json.ends_with(r#","type_name":"std::io::Error"}]"#));
+/// ```
+#[must_use]
+#[inline]
+pub fn print_error_chain_json<'a>(error: impl error::Error + 'a) -> impl fmt::Display + 'a {
+    JsonChain { error }
+}
+
+struct Logfmt<E> {
+    error: E,
+}
+
+impl<E: error::Error> fmt::Display for Logfmt<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chain = Chain::new(&self.error).enumerate().peekable();
+        while let Some((i, error)) = chain.next() {
+            match i {
+                0 => write!(f, "error=")?,
+                _ if chain.peek().is_none() => write!(f, " root=")?,
+                _ => write!(f, " cause{}=", i - 1)?,
             }
+            internal::write_json_string(f, &redact::Redacting(&error))?;
         }
         Ok(())
     }
 }
 
-/// Print the complete error chain of an error, separated with colons
+/// Print the error chain as logfmt key-value pairs, e.g.
+/// `error="outer" cause0="middle" root="root"`
+///
+/// Unlike parsing the `{}` format of [print_error_chain] back apart, this keeps
+/// each message separate even when it contains colons. Values are always
+/// quoted, and escaped in the same way as JSON strings.
+///
+/// ```
+/// let e = ees::wrap!(ees::err!("key: expected string"), "invalid config");
+/// assert_eq!(
+///     ees::logfmt(&e).to_string(),
+///     r#"error="invalid config" root="key: expected string""#
+/// );
+/// ```
 #[must_use]
 #[inline]
-pub fn print_error_chain<'a>(error: impl error::Error + 'a) -> impl fmt::Display + 'a {
-    ErrorChain {
-        error: Box::new(error),
+pub fn logfmt<'a>(error: impl error::Error + 'a) -> impl fmt::Display + 'a {
+    Logfmt { error }
+}
+
+/// The layout used by [write_error_chain]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChainStyle {
+    /// All errors on a single line, separated with colons (the `{}` format of
+    /// [print_error_chain])
+    #[default]
+    Compact,
+    /// The outermost error, followed by a "Caused by:" section listing each
+    /// cause on its own line (the `{:#}` format of [print_error_chain])
+    CausedBy,
+}
+
+/// Write the complete error chain of an error directly into a [fmt::Write]
+/// implementation, such as a `String` or a [fmt::Formatter]
+///
+/// ```
+/// let mut buffer = String::from("request failed: ");
+/// let e = ees::wrap!(ees::err!("timed out"), "connection lost");
+/// ees::write_error_chain(&mut buffer, &e, ees::ChainStyle::Compact).unwrap();
+/// assert_eq!(buffer, "request failed: connection lost: timed out");
+/// ```
+pub fn write_error_chain(
+    writer: &mut (impl fmt::Write + ?Sized),
+    error: impl error::Error,
+    style: ChainStyle,
+) -> fmt::Result {
+    let chain = print_error_chain(error);
+    match style {
+        ChainStyle::Compact => write!(writer, "{}", chain),
+        ChainStyle::CausedBy => write!(writer, "{:#}", chain),
+    }
+}
+
+/// Write the complete error chain of an error directly into an [io::Write](std::io::Write)
+/// implementation, such as a file or `stderr`
+///
+/// See [write_error_chain] for writing into a [fmt::Write] implementation.
+#[cfg(feature = "std")]
+pub fn write_error_chain_io(
+    writer: &mut (impl std::io::Write + ?Sized),
+    error: impl error::Error,
+    style: ChainStyle,
+) -> std::io::Result<()> {
+    let chain = print_error_chain(error);
+    match style {
+        ChainStyle::Compact => write!(writer, "{}", chain),
+        ChainStyle::CausedBy => write!(writer, "{:#}", chain),
+    }
+}
+
+/// Iterate over the errors in the chain, starting with the outermost error
+/// and ending with the root cause
+///
+/// This is the same as [ErrorChain::iter], but borrows the error for as long
+/// as the error itself can be borrowed.
+///
+/// ```
+/// let e = ees::wrap!(ees::err!("root"), "outer");
+/// let messages: Vec<String> = ees::chain(&e).map(|e| e.to_string()).collect();
+/// assert_eq!(messages, ["outer", "root"]);
+/// ```
+#[must_use]
+pub fn chain<'a>(error: BorrowedErrorRef<'a>) -> Chain<'a> {
+    Chain::new(error)
+}
+
+/// Returns the innermost error in the chain
+///
+/// ```
+/// let e = ees::wrap!(ees::err!("root"), "outer");
+/// assert_eq!(ees::root_cause(&e).to_string(), "root");
+/// ```
+#[must_use]
+pub fn root_cause<'a>(error: BorrowedErrorRef<'a>) -> BorrowedErrorRef<'a> {
+    Chain::new(error)
+        .last()
+        .expect("an error chain is never empty")
+}
+
+/// Returns the outermost error in the chain that has the type `E`
+///
+/// Errors created with [wrap!] have private types, so `downcast_ref` on the
+/// wrapped error doesn't find the original error. This searches the whole
+/// chain instead.
+///
+/// ```
+/// let e = ees::wrap!(std::fmt::Error, "failed to render page");
+/// assert!(ees::find_cause::<std::fmt::Error>(&e).is_some());
+/// ```
+#[must_use]
+pub fn find_cause<E: error::Error + 'static>(error: ErrorRef<'_>) -> Option<&E> {
+    chain::static_chain(error).find_map(|error| error.downcast_ref())
+}
+
+/// Returns the message of each error in the chain, starting with the
+/// outermost error
+///
+/// ```
+/// let e = ees::wrap!(ees::err!("root"), "outer");
+/// assert_eq!(ees::chain_messages(&e), ["outer", "root"]);
+/// ```
+#[must_use]
+pub fn chain_messages(error: impl error::Error) -> Vec<String> {
+    Chain::new(&error).map(|error| error.to_string()).collect()
+}
+
+/// Whether two error chains have the same length, and errors with the same
+/// messages
+///
+/// ```
+/// let a = ees::wrap!(ees::err!("root"), "outer");
+/// let b = ees::wrap!(std::io::Error::other("root"), "outer");
+/// assert!(ees::chain_eq(&a, &b));
+/// ```
+#[must_use]
+pub fn chain_eq(a: impl error::Error, b: impl error::Error) -> bool {
+    let messages = |error| Chain::new(error).map(|error| error.to_string());
+    messages(&a).eq(messages(&b))
+}
+
+/// A hash of the messages in the chain, e.g. for grouping identical failures
+/// in a monitoring system
+///
+/// The digest only depends on the messages, and is stable across runs,
+/// platforms and versions of this crate. Chains that are equal according to
+/// [chain_eq] have the same digest.
+///
+/// ```
+/// let a = ees::wrap!(ees::err!("root"), "outer");
+/// let b = ees::wrap!(std::io::Error::other("root"), "outer");
+/// assert_eq!(ees::chain_digest(&a), ees::chain_digest(&b));
+/// assert_eq!(format!("{:016x}", ees::chain_digest(&a)), "22a164a4d158c432");
+/// ```
+#[must_use]
+pub fn chain_digest(error: impl error::Error) -> u64 {
+    // 64-bit FNV-1a, which unlike `DefaultHasher` is specified
+    struct Fnv(u64);
+
+    impl fmt::Write for Fnv {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for &byte in s.as_bytes() {
+                self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+            Ok(())
+        }
+    }
+
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    for error in Chain::new(&error) {
+        fmt::Write::write_fmt(&mut hasher, format_args!("{}", error)).expect("hashing never fails");
+        // separate the messages with a byte that doesn't occur in UTF-8
+        hasher.0 = (hasher.0 ^ 0xff).wrapping_mul(0x0100_0000_01b3);
+    }
+    hasher.0
+}
+
+/// An error chain reconstructed from its messages, e.g. after being received
+/// from a remote service
+///
+/// Each message becomes its own layer in the [source](error::Error::source)
+/// chain, so the error renders identically to the original through
+/// [print_error_chain].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializedError {
+    message: String,
+    source: Option<Box<DeserializedError>>,
+}
+
+impl DeserializedError {
+    /// Build an error chain from its messages, starting with the outermost
+    /// error and ending with the root cause
+    ///
+    /// Returns `None` if there are no messages.
+    pub fn from_messages<I>(messages: I) -> Option<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let messages: Vec<String> = messages.into_iter().map(Into::into).collect();
+        messages.into_iter().rev().fold(None, |source, message| {
+            Some(Self {
+                message,
+                source: source.map(Box::new),
+            })
+        })
+    }
+
+    /// Copy the messages of an error and all of its sources
+    #[must_use]
+    pub fn snapshot(error: &(dyn error::Error + '_)) -> Self {
+        let messages = Chain::new(error).map(|error| error.to_string());
+        Self::from_messages(messages).expect("an error chain is never empty")
+    }
+
+    /// The message of this layer, excluding its sources
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for DeserializedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for DeserializedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source.as_ref().map(|source| source.as_ref() as _)
+    }
+}
+
+/// Convert an error that is not `Send` or `Sync` into an [Error]
+///
+/// Since the original error can't be stored, this takes a snapshot of the
+/// message of every error in its chain (see [DeserializedError::snapshot]).
+/// The messages are preserved, but downcasting to the original error types is
+/// no longer possible.
+#[must_use]
+pub fn from_boxed(error: LocalError) -> Error {
+    Box::new(DeserializedError::snapshot(error.as_ref()))
+}
+
+/// Returns the source location of the [err!], [wrap!] or [wrap_with!]
+/// invocation that created this error, or `None` if the error was created some
+/// other way
+#[must_use]
+pub fn location(error: ErrorRef<'_>) -> Option<&'static panic::Location<'static>> {
+    internal::location(error)
+}
+
+/// A named value attached to an error with [wrap!]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    name: &'static str,
+    value: String,
+}
+
+impl Field {
+    /// The name of this field
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The formatted value of this field
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.value)
+    }
+}
+
+/// Returns the fields attached to this error with [wrap!]
+///
+/// Only the given error is inspected, not its sources.
+#[must_use]
+pub fn fields<'a>(error: ErrorRef<'a>) -> &'a [Field] {
+    internal::fields(error)
+}
+
+/// How serious an error is, set with e.g. `err!(@warn, "...")`
+///
+/// The severities are ordered from least to most severe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something went wrong, but the operation could still be completed
+    Warning,
+    /// The operation failed (this is the default)
+    #[default]
+    Error,
+    /// The failure is unrecoverable
+    Fatal,
+}
+
+/// Returns the highest severity set with [err!] anywhere in the chain, or
+/// [Severity::Error] if there is none
+///
+/// ```
+/// let e = ees::wrap!(ees::err!(@warn, "cache is stale"), "loading cache");
+/// assert_eq!(ees::severity(&e), ees::Severity::Warning);
+/// ```
+#[must_use]
+pub fn severity(error: ErrorRef<'_>) -> Severity {
+    chain::static_chain(error)
+        .filter_map(internal::severity)
+        .max()
+        .unwrap_or_default()
+}
+
+/// A stable, machine-readable identifier for an error, set with e.g.
+/// `err!(code = "E0042", "...")`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// A string code, such as `"E0042"` or `"config.missing"`
+    Name(&'static str),
+    /// A numeric code
+    Number(u32),
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+impl From<&'static str> for ErrorCode {
+    fn from(name: &'static str) -> Self {
+        Self::Name(name)
     }
 }
 
+impl From<u32> for ErrorCode {
+    fn from(number: u32) -> Self {
+        Self::Number(number)
+    }
+}
+
+/// Returns the code set with [err!] on the outermost error in the chain that
+/// has one
+///
+/// ```
+/// let e = ees::wrap!(ees::err!(code = "E0042", "missing field"), "invalid request");
+/// assert_eq!(ees::error_code(&e), Some(ees::ErrorCode::Name("E0042")));
+/// ```
+#[must_use]
+pub fn error_code(error: ErrorRef<'_>) -> Option<ErrorCode> {
+    chain::static_chain(error).find_map(internal::error_code)
+}
+
+/// A broad category of errors, set with e.g. `err!(kind = Kind::NotFound, "...")`,
+/// `wrap!(e, kind = Kind::Transient, "...")` or [ResultExt::with_kind]
+///
+/// The kind is used by [is_transient] and as the default status of
+/// [HttpError], and can be used for choosing exit codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The error was caused by invalid input or usage
+    User,
+    /// The operation might succeed if it is repeated
+    Transient,
+    /// A bug or an unexpected condition
+    Internal,
+    /// Something that was looked up doesn't exist
+    NotFound,
+}
+
+/// A short random identifier of an error, which users can quote in support
+/// requests to find the error in server logs
+///
+/// With the `error-ids` feature, every error created with [err!] gets a new
+/// ID, and errors created with [wrap!] keep the ID of their source. The ID is
+/// printed before the report of a [MainError], e.g.
+/// `[id: 9f3c2a] Error: failed to connect`, and can be logged by servers with
+/// [error_id]. IDs are not guaranteed to be unique, but collisions between
+/// errors that occur at a similar time are unlikely.
+#[cfg(feature = "error-ids")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorId(u32);
+
+#[cfg(feature = "error-ids")]
+impl ErrorId {
+    pub(crate) fn generate() -> Self {
+        use std::hash::{BuildHasher, Hasher};
+
+        // every `RandomState` has different keys, so hashing nothing already
+        // produces a random number
+        let hash = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        Self((hash & 0xff_ffff) as u32)
+    }
+}
+
+/// Written as 6 hexadecimal digits, e.g. `9f3c2a`
+#[cfg(feature = "error-ids")]
+impl fmt::Display for ErrorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:06x}", self.0)
+    }
+}
+
+/// Returns the [ErrorId] of the outermost error in the chain that has one
+///
+/// ```
+/// let e = ees::err!("connection refused");
+/// let id = ees::error_id(&e).unwrap();
+/// let e = ees::wrap!(e, "failed to connect");
+/// assert_eq!(ees::error_id(&e), Some(id));
+/// ```
+#[cfg(feature = "error-ids")]
+#[must_use]
+pub fn error_id(error: ErrorRef<'_>) -> Option<ErrorId> {
+    chain::static_chain(error).find_map(internal::error_id)
+}
+
+/// Returns the kind set with [err!], [wrap!] or [ResultExt::with_kind] on the
+/// outermost error in the chain that has one
+///
+/// ```
+/// let e = ees::err!(kind = ees::Kind::NotFound, "no such user");
+/// let e = ees::wrap!(e, "lookup failed");
+/// assert_eq!(ees::kind_of(&e), Some(ees::Kind::NotFound));
+/// ```
+#[must_use]
+pub fn kind_of(error: ErrorRef<'_>) -> Option<Kind> {
+    chain::static_chain(error).find_map(internal::kind)
+}
+
 /// This type wraps an arbitrary error, and is intended for use in the `main()` method
+///
+/// Errors with a [Severity] of `Warning` are reported as warnings, and exit
+/// with code 0 by default. The [ErrorCode] of the error, if any, is included
+/// in the report, e.g. `Error[E0042]: missing field`, and if the code has a
+/// [CatalogEntry] with a URL, the report ends with a link to it. The message
+/// at the start of the report can be replaced with a friendlier one, see
+/// [Translations].
+///
+/// With `--cfg ees_nightly` (see the [crate documentation](crate)), the
+/// `Debug` output ends with a "Stack backtrace:" section if an error in the
+/// chain provides a captured [Backtrace](std::backtrace::Backtrace).
+///
+/// Setting the `EES_VERBOSE` environment variable to `0` prints the error
+/// chain on a single line instead of the "Caused by:" report, and setting it
+/// to `1` includes the source location of each error.
+///
+/// With the `timestamps` feature, the report starts with the time the
+/// `MainError` was created and the name of the thread it was created on, e.g.
+/// `[2026-10-14T09:30:45Z thread 'main'] Error: ...`. Times are in UTC. When
+/// the error is returned from `main()`, this follows the `Error: ` printed by
+/// the standard library instead.
+///
+/// With the `error-ids` feature, the [ErrorId] of the error is printed before
+/// the report as well, e.g. `[id: 9f3c2a] Error: ...`.
+///
+/// The alternate `Debug` format, `{:#?}`, writes the report without the
+/// timestamp, the ID, the breadcrumbs and the diagnostics.
+///
+/// With the `diagnostics` feature, the report ends with a "Diagnostics:"
+/// section listing the executable, its version (see [set_version]), the
+/// command-line arguments and the operating system, so that bug reports
+/// pasted by users include them.
+///
+/// `MainError` doesn't implement [Error](error::Error): `?` relies on
+/// `MainError` implementing `From` for every error type, which would then
+/// include `MainError` itself and conflict with the standard library's
+/// `impl<T> From<T> for T`. Nest the wrapped error instead, which is returned
+/// by [into_inner](Self::into_inner).
+#[cfg(feature = "std")]
 pub struct MainError {
     error: Error,
+    exit_code: Option<u8>,
+    quiet_broken_pipe: bool,
+    #[cfg(feature = "timestamps")]
+    context: timestamp::Context,
+}
+
+#[cfg(feature = "std")]
+impl MainError {
+    /// Set the exit code the process should terminate with (the default is 1,
+    /// or 0 for warnings)
+    ///
+    /// Returning `Err(MainError)` from `main()` always exits with code 1, because
+    /// that behaviour is hard-coded in the standard library. To use a custom exit
+    /// code, report the error through [Termination](process::Termination)
+    /// instead:
+    ///
+    /// ```no_run
+    /// use std::process::{ExitCode, Termination};
+    ///
+    /// fn run() -> ees::MainResult {
+    ///     Err(ees::MainError::from(ees::err!("invalid usage")).with_exit_code(2))
+    /// }
+    ///
+    /// fn main() -> ExitCode {
+    ///     match run() {
+    ///         Ok(()) => ExitCode::SUCCESS,
+    ///         Err(e) => e.report(),
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_exit_code(mut self, exit_code: u8) -> Self {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    /// Don't report the error if it was caused by a broken pipe, e.g. when the
+    /// output is piped into `head`, and exit with code 0 unless a different
+    /// exit code is set
+    ///
+    /// Like [with_exit_code](Self::with_exit_code), this requires the error to
+    /// be reported through [Termination](process::Termination).
+    #[must_use]
+    pub fn quiet_on_broken_pipe(mut self) -> Self {
+        self.quiet_broken_pipe = true;
+        self
+    }
+
+    /// The exit code the process should terminate with
+    ///
+    /// Unless it has been set with [with_exit_code](Self::with_exit_code),
+    /// the exit code is looked up in the [ExitMap], if one is installed.
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        if self.is_quiet() {
+            return self.exit_code.unwrap_or(0);
+        }
+        if let Some(exit_code) = self.exit_code {
+            return exit_code;
+        }
+        match self.severity() {
+            Severity::Warning => 0,
+            Severity::Error | Severity::Fatal => {
+                exit_map::exit_code(self.error.as_ref()).unwrap_or(1)
+            }
+        }
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.quiet_broken_pipe
+            && chain::static_chain(self.error.as_ref()).any(|error| {
+                error
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|error| error.kind() == std::io::ErrorKind::BrokenPipe)
+            })
+    }
+
+    /// The severity of the wrapped error, see [severity]
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        severity(self.error.as_ref())
+    }
+
+    /// The wrapped error
+    ///
+    /// ```
+    /// let e = ees::MainError::from(std::fmt::Error);
+    /// assert!(e.as_error().is::<std::fmt::Error>());
+    /// ```
+    #[must_use]
+    pub fn as_error(&self) -> ErrorRef<'_> {
+        self.error.as_ref()
+    }
+
+    /// Unwrap the error, e.g. to downcast it or to nest it in another error
+    #[must_use]
+    pub fn into_inner(self) -> Error {
+        self.error
+    }
 }
 
+#[cfg(feature = "std")]
 impl fmt::Display for MainError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:#}", print_error_chain(self.error.as_ref()))
+        if let Some(hook) = report_hook() {
+            return hook(self.error.as_ref(), f);
+        }
+        self.write_chain(f)
     }
 }
 
+#[cfg(feature = "std")]
 impl fmt::Debug for MainError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:#}", print_error_chain(self.error.as_ref()))
+        if f.alternate() {
+            return self.write_report(f);
+        }
+        self.write_framed(f, "")
     }
 }
 
-impl<E: Into<Error>> From<E> for MainError {
-    fn from(error: E) -> Self {
-        Self {
-            error: error.into(),
+/// The report of a [MainError], with its header
+#[cfg(feature = "std")]
+struct FullReport<'a> {
+    error: &'a MainError,
+    header: String,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for FullReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.write_framed(f, &self.header)
+    }
+}
+
+#[cfg(feature = "std")]
+impl MainError {
+    /// The report, preceded by the timestamp and ID and followed by the
+    /// breadcrumbs and diagnostics, depending on the enabled features
+    fn write_framed(&self, f: &mut fmt::Formatter<'_>, header: &str) -> fmt::Result {
+        #[cfg(feature = "timestamps")]
+        let context = &self.context;
+        #[cfg(not(feature = "timestamps"))]
+        let context = "";
+        #[cfg(feature = "error-ids")]
+        let id = error_id(self.error.as_ref())
+            .map(|id| format!("[id: {}] ", id))
+            .unwrap_or_default();
+        #[cfg(not(feature = "error-ids"))]
+        let id = "";
+        #[cfg(feature = "diagnostics")]
+        let diagnostics = diagnostics::Diagnostics::capture();
+        #[cfg(not(feature = "diagnostics"))]
+        let diagnostics = "";
+        write!(f, "{}{}{}", context, id, header)?;
+        self.write_report(f)?;
+        write!(f, "{}{}", breadcrumbs::Breadcrumbs::capture(), diagnostics)
+    }
+
+    fn write_report(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(hook) = report_hook() {
+            return hook(self.error.as_ref(), f);
+        }
+        #[cfg(feature = "color")]
+        {
+            if color::enabled() && Verbosity::from_env() == Verbosity::Normal {
+                match translate::translate(self.error.as_ref()) {
+                    Some(translated) => color::write_report(f, &translated)?,
+                    None => color::write_report(f, self.error.as_ref())?,
+                }
+                report::write_sections(f, self.error.as_ref())?;
+                self.write_documentation(f)?;
+                return self.write_backtrace(f);
+            }
         }
+        self.write_chain(f)?;
+        self.write_backtrace(f)
     }
 }
 
-/// A convenient way to return arbitrary errors from `main()`
-pub type MainResult = std::result::Result<(), MainError>;
+#[cfg(feature = "std")]
+impl MainError {
+    /// The first backtrace provided by an error in the chain, if it was
+    /// captured (see [Backtrace::capture](std::backtrace::Backtrace::capture))
+    #[cfg(ees_nightly)]
+    pub(crate) fn backtrace(&self) -> Option<&dyn fmt::Display> {
+        use std::backtrace::{Backtrace, BacktraceStatus};
 
-/// Construct an error on the fly
-#[macro_export]
-macro_rules! err {
-    ($fmt:expr) => {
-        $crate::internal::error_from_args(::std::format_args!($fmt))
-    };
+        chain::static_chain(self.error.as_ref())
+            .find_map(error::request_ref::<Backtrace>)
+            .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+            .map(|backtrace| backtrace as _)
+    }
 
-    ($fmt:expr, $($args:tt)*) => {
-        $crate::internal::error_from_args(::std::format_args!($fmt, $($args)*))
-    };
+    #[cfg(not(ees_nightly))]
+    pub(crate) fn backtrace(&self) -> Option<&dyn fmt::Display> {
+        None
+    }
+
+    fn write_backtrace(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.backtrace() {
+            Some(backtrace) => write!(f, "\n\nStack backtrace:\n{}", backtrace),
+            None => Ok(()),
+        }
+    }
+
+    fn write_chain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match translate::translate(self.error.as_ref()) {
+            Some(translated) => Self::write_verbosity(f, print_error_chain(translated))?,
+            None => Self::write_verbosity(f, print_error_chain(self.error.as_ref()))?,
+        }
+        self.write_documentation(f)
+    }
+
+    fn write_verbosity<E: error::Error>(
+        f: &mut fmt::Formatter<'_>,
+        chain: ErrorChain<E>,
+    ) -> fmt::Result {
+        match Verbosity::from_env() {
+            Verbosity::Compact => write!(f, "{}", chain),
+            Verbosity::Normal => write!(f, "{:#}", chain),
+            Verbosity::Verbose => write!(f, "{:+#}", chain),
+        }
+    }
+
+    /// Link to the documentation of the error code, if it has been registered
+    /// with a URL (see [CatalogEntry])
+    fn write_documentation(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = error_code(self.error.as_ref()).and_then(|code| match code {
+            ErrorCode::Name(code) => catalog_entry(code)?.documentation(),
+            ErrorCode::Number(_) => None,
+        });
+        match url {
+            Some(url) => write!(f, "\n\nFor more information about this error, see {}", url),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How much detail a [MainError] report includes, chosen with the
+/// `EES_VERBOSE` environment variable
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq)]
+enum Verbosity {
+    /// `EES_VERBOSE=0`: the chain on a single line
+    Compact,
+    /// The "Caused by:" report
+    Normal,
+    /// `EES_VERBOSE=1`: the "Caused by:" report, including source locations
+    Verbose,
+}
+
+#[cfg(feature = "std")]
+impl Verbosity {
+    fn from_env() -> Self {
+        match std::env::var_os("EES_VERBOSE") {
+            Some(value) if value == "0" => Self::Compact,
+            Some(value) if value.is_empty() => Self::Normal,
+            Some(_) => Self::Verbose,
+            None => Self::Normal,
+        }
+    }
+}
+
+/// A function that renders the error reported by a [MainError], see
+/// [set_report_hook]
+#[cfg(feature = "std")]
+pub type ReportHook = fn(ErrorRef<'_>, &mut fmt::Formatter<'_>) -> fmt::Result;
+
+#[cfg(feature = "std")]
+static REPORT_HOOK: std::sync::RwLock<Option<ReportHook>> = std::sync::RwLock::new(None);
+
+/// Customize how a [MainError] is rendered, e.g. to add version information
+/// or a link to the documentation
+///
+/// The hook replaces the default "Caused by:" report, and is used by both the
+/// `Display` and `Debug` impls of [MainError]. Setting it again replaces the
+/// previous hook.
+///
+/// ```
+/// use std::fmt;
+///
+/// fn report(error: ees::ErrorRef<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///     write!(f, "{:#}", ees::print_error_chain(error))?;
+///     write!(f, "\n\nPlease report this at https://example.com/issues")
+/// }
+///
+/// ees::set_report_hook(report);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_report_hook(hook: ReportHook) {
+    // the hook is a plain function pointer, so a poisoned lock holds valid data
+    let mut report_hook = REPORT_HOOK.write().unwrap_or_else(|e| e.into_inner());
+    *report_hook = Some(hook);
+}
+
+#[cfg(feature = "std")]
+fn report_hook() -> Option<ReportHook> {
+    *REPORT_HOOK.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A function that translates the message of an error in a chain, given the
+/// message and the [ErrorCode] of the error if it has one, see [set_localizer]
+///
+/// Returning `None` keeps the original message.
+#[cfg(feature = "std")]
+pub type Localizer = fn(&str, Option<&str>) -> Option<String>;
+
+#[cfg(feature = "std")]
+static LOCALIZER: std::sync::RwLock<Option<Localizer>> = std::sync::RwLock::new(None);
+
+/// Translate or post-process every message printed by [print_error_chain],
+/// [ChainFormat] and [MainError], e.g. for a localized command-line interface
+///
+/// Errors created with [err!] can be looked up by their code, and errors from
+/// other crates by their message. Fields and source locations are appended to
+/// translated messages as usual. Setting it again replaces the previous
+/// localizer.
+///
+/// ```
+/// fn german(message: &str, code: Option<&str>) -> Option<String> {
+///     match code {
+///         Some("E0042") => Some(message.replace("missing field", "fehlendes Feld")),
+///         _ => None,
+///     }
+/// }
+///
+/// ees::set_localizer(german);
+/// let e = ees::err!(code = "E0042", "missing field `name`");
+/// assert_eq!(ees::print_error_chain(&e).to_string(), "fehlendes Feld `name`");
+/// ```
+#[cfg(feature = "std")]
+pub fn set_localizer(localizer: Localizer) {
+    // the localizer is a plain function pointer, so a poisoned lock holds
+    // valid data
+    let mut current = LOCALIZER.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(localizer);
+}
+
+#[cfg(feature = "std")]
+fn localizer() -> Option<Localizer> {
+    *LOCALIZER.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A function that is called whenever an error is created, see
+/// [set_creation_hook]
+#[cfg(feature = "std")]
+pub type CreationHook = fn(&str, &'static panic::Location<'static>);
+
+#[cfg(feature = "std")]
+static CREATION_HOOK: std::sync::RwLock<Option<CreationHook>> = std::sync::RwLock::new(None);
+// checked before taking the lock, so that creating errors without a hook only
+// costs an atomic load
+#[cfg(feature = "std")]
+static HAS_CREATION_HOOK: core::sync::atomic::AtomicBool =
+    core::sync::atomic::AtomicBool::new(false);
+
+/// Call a function with the message and the caller location of every error
+/// created with [err!] or [wrap!], e.g. to count errors per call site with a
+/// metrics library
+///
+/// Setting it again replaces the previous hook.
+///
+/// ```
+/// use std::panic::Location;
+///
+/// fn count(message: &str, location: &'static Location<'static>) {
+///     println!("error at {}:{}: {}", location.file(), location.line(), message);
+/// }
+///
+/// ees::set_creation_hook(count);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_creation_hook(hook: CreationHook) {
+    // the hook is a plain function pointer, so a poisoned lock holds valid data
+    let mut creation_hook = CREATION_HOOK.write().unwrap_or_else(|e| e.into_inner());
+    *creation_hook = Some(hook);
+    HAS_CREATION_HOOK.store(true, core::sync::atomic::Ordering::Release);
+}
+
+#[cfg(feature = "std")]
+#[inline]
+fn call_creation_hook(message: &str, location: &'static panic::Location<'static>) {
+    if !HAS_CREATION_HOOK.load(core::sync::atomic::Ordering::Acquire) {
+        return;
+    }
+    let hook = *CREATION_HOOK.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(hook) = hook {
+        hook(message, location);
+    }
+}
+
+#[cfg(feature = "color")]
+mod color {
+    use std::{env, error, fmt, io};
+
+    /// Colors are used if stderr is a terminal, and `NO_COLOR` is not set
+    pub(crate) fn enabled() -> bool {
+        use io::IsTerminal;
+
+        env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stderr().is_terminal()
+    }
+
+    /// Write the top-level message in bold red, and the causes dimmed
+    pub(crate) fn write_report(
+        f: &mut fmt::Formatter<'_>,
+        error: &dyn error::Error,
+    ) -> fmt::Result {
+        let flags = crate::chain::Flags {
+            alternate: true,
+            plus: false,
+            type_names: None,
+        };
+        let layer = crate::chain::Layer::new(error, flags);
+        write!(f, "\x1b[1;31m{}\x1b[0m", layer)?;
+        if error.source().is_some() {
+            write!(f, "\x1b[2m")?;
+            let mut causes = crate::Chain::new(error).skip_empty();
+            causes.next();
+            crate::chain::write_causes(f, causes, flags, &crate::ChainFormat::new())?;
+            write!(f, "\x1b[0m")?;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        struct Report(crate::Error);
+
+        impl std::fmt::Display for Report {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                super::write_report(f, self.0.as_ref())
+            }
+        }
+
+        #[test]
+        fn colored_report() {
+            let e = crate::err!("root");
+            let e: crate::Error = crate::wrap!(e, "failed to do work").into();
+            assert_eq!(
+                Report(e).to_string(),
+                "\x1b[1;31mfailed to do work\x1b[0m\x1b[2m\n\nCaused by:\n    root\x1b[0m"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "timestamps")]
+mod timestamp {
+    use std::{fmt, thread, time};
+
+    /// When and where a [MainError](crate::MainError) was created
+    pub(crate) struct Context {
+        time: time::SystemTime,
+        thread: Option<String>,
+    }
+
+    impl Context {
+        pub(crate) fn capture() -> Self {
+            Self {
+                time: time::SystemTime::now(),
+                thread: thread::current().name().map(str::to_string),
+            }
+        }
+    }
+
+    /// Written as e.g. `[2026-10-14T09:30:45Z thread 'main'] `
+    impl fmt::Display for Context {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // times before 1970 are clamped, the clock is most likely wrong
+            let secs = self
+                .time
+                .duration_since(time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            let (year, month, day) = civil_from_days(secs / 86400);
+            let secs = secs % 86400;
+            write!(
+                f,
+                "[{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z thread '{}'] ",
+                year,
+                month,
+                day,
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60,
+                self.thread.as_deref().unwrap_or("<unnamed>")
+            )
+        }
+    }
+
+    /// Convert days since 1970-01-01 into a date in the proleptic Gregorian
+    /// calendar, see <https://howardhinnant.github.io/date_algorithms.html>
+    fn civil_from_days(days: u64) -> (u64, u64, u64) {
+        // shift the epoch to 0000-03-01, so that leap days are at the end of
+        // each 400-year era
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = era * 400 + year_of_era + u64::from(month <= 2);
+        (year, month, day)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        #[test]
+        fn context() {
+            let context = super::Context {
+                time: UNIX_EPOCH + Duration::from_secs(1_791_970_245),
+                thread: Some("main".to_string()),
+            };
+            assert_eq!(context.to_string(), "[2026-10-14T09:30:45Z thread 'main'] ");
+            let context = super::Context {
+                time: UNIX_EPOCH + Duration::from_secs(951_782_400),
+                thread: None,
+            };
+            assert_eq!(
+                context.to_string(),
+                "[2000-02-29T00:00:00Z thread '<unnamed>'] "
+            );
+        }
+    }
+}
+
+/// Set the version included in the diagnostics of [MainError] reports, which
+/// is usually the version of the application's crate:
+///
+/// ```
+/// ees::set_version(env!("CARGO_PKG_VERSION"));
+/// ```
+#[cfg(feature = "diagnostics")]
+pub fn set_version(version: &str) {
+    let mut current = diagnostics::VERSION
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    *current = Some(version.to_string());
+}
+
+#[cfg(feature = "diagnostics")]
+mod diagnostics {
+    use std::{env, ffi::OsString, fmt, path::Path, sync::RwLock};
+
+    pub(crate) static VERSION: RwLock<Option<String>> = RwLock::new(None);
+
+    /// Information about the invocation of the program, for bug reports
+    pub(crate) struct Diagnostics {
+        executable: Option<String>,
+        version: Option<String>,
+        arguments: Vec<OsString>,
+        os: &'static str,
+        arch: &'static str,
+    }
+
+    impl Diagnostics {
+        pub(crate) fn capture() -> Self {
+            let mut args = env::args_os();
+            let executable = args.next().and_then(|arg0| {
+                Some(Path::new(&arg0).file_name()?.to_string_lossy().into_owned())
+            });
+            Self {
+                executable,
+                version: VERSION.read().unwrap_or_else(|e| e.into_inner()).clone(),
+                arguments: args.collect(),
+                os: env::consts::OS,
+                arch: env::consts::ARCH,
+            }
+        }
+    }
+
+    /// Written as a "Diagnostics:" section, after an empty line
+    impl fmt::Display for Diagnostics {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "\n\nDiagnostics:")?;
+            if let Some(executable) = &self.executable {
+                write!(f, "\n    executable: {}", executable)?;
+            }
+            if let Some(version) = &self.version {
+                write!(f, "\n    version: {}", version)?;
+            }
+            write!(f, "\n    arguments: {:?}", self.arguments)?;
+            write!(f, "\n    os: {} ({})", self.os, self.arch)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn diagnostics() {
+            let diagnostics = super::Diagnostics {
+                executable: Some("myapp".to_string()),
+                version: Some("1.2.3".to_string()),
+                arguments: vec!["--path".into(), "a b".into()],
+                os: "linux",
+                arch: "x86_64",
+            };
+            assert_eq!(
+                diagnostics.to_string(),
+                "\n\nDiagnostics:\n    executable: myapp\n    version: 1.2.3\n    \
+                 arguments: [\"--path\", \"a b\"]\n    os: linux (x86_64)"
+            );
+            let diagnostics = super::Diagnostics {
+                executable: None,
+                version: None,
+                arguments: Vec::new(),
+                os: "windows",
+                arch: "aarch64",
+            };
+            assert_eq!(
+                diagnostics.to_string(),
+                "\n\nDiagnostics:\n    arguments: []\n    os: windows (aarch64)"
+            );
+
+            let e = crate::MainError::from(crate::err!("failed"));
+            assert!(format!("{:?}", e).contains("failed\n\nDiagnostics:\n"));
+            assert_eq!(format!("{:#?}", e), "failed");
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Into<Error>> From<E> for MainError {
+    fn from(error: E) -> Self {
+        Self {
+            error: error.into(),
+            exit_code: None,
+            quiet_broken_pipe: false,
+            #[cfg(feature = "timestamps")]
+            context: timestamp::Context::capture(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl process::Termination for MainError {
+    fn report(self) -> process::ExitCode {
+        if self.is_quiet() {
+            return process::ExitCode::from(self.exit_code());
+        }
+        self.print();
+        process::ExitCode::from(self.exit_code())
+    }
+}
+
+#[cfg(feature = "std")]
+impl MainError {
+    /// Print the report to stderr, pass the error to the [Reporter], and write
+    /// the crash report (see [set_crash_report_path])
+    pub(crate) fn print(&self) {
+        reporter::report(self.error.as_ref());
+        eprintln!("{}", self.full_report());
+        crash_report::write(self);
+    }
+
+    /// The report, including the header
+    pub(crate) fn full_report(&self) -> String {
+        let program_name = PROGRAM_NAME.read().unwrap_or_else(|e| e.into_inner());
+        let report = FullReport {
+            error: self,
+            header: self.header(program_name.as_deref()),
+        };
+        report.to_string()
+    }
+
+    /// The text printed before the error chain, e.g. `Error[E0042]: ` or
+    /// `myapp: error[E0042]: `
+    fn header(&self, program_name: Option<&str>) -> String {
+        let label = match self.severity() {
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+            Severity::Fatal => "Fatal error",
+        };
+        let mut header = match program_name {
+            // Unix tools conventionally use lowercase labels after their name
+            Some(name) => format!("{}: {}", name, label.to_lowercase()),
+            None => label.to_string(),
+        };
+        if let Some(code) = error_code(self.error.as_ref()) {
+            header.push_str(&format!("[{}]", code));
+        }
+        header.push_str(": ");
+        header
+    }
+}
+
+#[cfg(feature = "std")]
+static PROGRAM_NAME: std::sync::RwLock<Option<String>> = std::sync::RwLock::new(None);
+
+/// Prefix errors reported by [MainError] with the name of the program, e.g.
+/// `myapp: error: file not found`
+///
+/// ```
+/// ees::set_program_name("myapp");
+/// ```
+#[cfg(feature = "std")]
+pub fn set_program_name(name: &str) {
+    let mut program_name = PROGRAM_NAME.write().unwrap_or_else(|e| e.into_inner());
+    *program_name = Some(name.to_string());
+}
+
+/// Like [set_program_name], using the file name of the executable as given
+/// by `argv[0]`
+///
+/// Nothing is changed if `argv[0]` is not available.
+#[cfg(feature = "std")]
+pub fn set_program_name_from_args() {
+    let name = std::env::args_os().next().and_then(|arg0| {
+        let arg0 = std::path::PathBuf::from(arg0);
+        Some(arg0.file_name()?.to_string_lossy().into_owned())
+    });
+    if let Some(name) = name {
+        set_program_name(&name);
+    }
+}
+
+/// A convenient way to return arbitrary errors from `main()`
+///
+/// ```no_run
+/// # fn do_work() -> ees::Result<()> { Ok(()) }
+/// // Use ees::MainResult to automatically create nicely-
+/// // formatted error messages in the main() function
+/// fn main() -> ees::MainResult {
+///     do_work()?;
+///     do_work().map_err(
+///         // add additional context
+///         |e| ees::wrap!(e, "failed to do work"))?;
+///     Ok(())
+/// }
+/// ```
+///
+/// The success value can be any type that implements
+/// [Termination](process::Termination), e.g. an [ExitCode](process::ExitCode):
+///
+/// ```
+/// use std::process::ExitCode;
+///
+/// fn main() -> ees::MainResult<ExitCode> {
+///     let changed = false;
+///     Ok(if changed { ExitCode::from(2) } else { ExitCode::SUCCESS })
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub type MainResult<T = ()> = core::result::Result<T, MainError>;
+
+/// Run the body of a program, and exit the process when it returns
+///
+/// This is for programs that can't return a [MainResult] from `main()`, e.g.
+/// because a framework owns `main()`. If the closure fails, the error is
+/// reported in the same way as a [MainError] returned from `main()`, and the
+/// process exits with the [exit code](MainError::exit_code) of the error.
+/// Otherwise, it exits with code 0.
+///
+/// ```no_run
+/// ees::run(|| -> ees::Result<()> {
+///     let config = std::fs::read_to_string("config.toml")?;
+///     println!("{}", config);
+///     Ok(())
+/// })
+/// ```
+///
+/// Destructors of values outside of the closure don't run, because the
+/// process exits with [process::exit].
+#[cfg(feature = "std")]
+pub fn run<E: Into<MainError>>(body: impl FnOnce() -> core::result::Result<(), E>) -> ! {
+    let error = match body() {
+        Ok(()) => process::exit(0),
+        Err(error) => error.into(),
+    };
+    if !error.is_quiet() {
+        error.print();
+    }
+    process::exit(i32::from(error.exit_code()))
+}
+
+/// Construct an error on the fly
+///
+/// The message can be prefixed with a [Severity], either `@warn`, `@error`
+/// or `@fatal`, with an [ErrorCode], with an HTTP status (see [HttpError]),
+/// and with a [Kind]:
+///
+/// ```
+/// let e = ees::err!(@fatal, code = 17, kind = ees::Kind::Internal, "database is corrupted");
+/// assert_eq!(ees::severity(&e), ees::Severity::Fatal);
+/// assert_eq!(ees::error_code(&e), Some(ees::ErrorCode::Number(17)));
+/// assert_eq!(ees::kind_of(&e), Some(ees::Kind::Internal));
+/// ```
+///
+/// Alternatively, `source = ...` creates an error with a source, which is the
+/// same as using [wrap!]:
+///
+/// ```
+/// # let e = ees::err!("connection reset");
+/// let e = ees::err!(source = e, "failed to fetch {}", "index.html");
+/// assert_eq!(
+///     ees::print_error_chain(&e).to_string(),
+///     "failed to fetch index.html: connection reset"
+/// );
+/// ```
+#[macro_export]
+macro_rules! err {
+    (@build $builder:expr, @warn, $($rest:tt)+) => {
+        $crate::err!(@build $builder.severity($crate::Severity::Warning), $($rest)+)
+    };
+
+    (@build $builder:expr, @error, $($rest:tt)+) => {
+        $crate::err!(@build $builder.severity($crate::Severity::Error), $($rest)+)
+    };
+
+    (@build $builder:expr, @fatal, $($rest:tt)+) => {
+        $crate::err!(@build $builder.severity($crate::Severity::Fatal), $($rest)+)
+    };
+
+    (@build $builder:expr, code = $code:expr, $($rest:tt)+) => {
+        $crate::err!(@build $builder.code($code), $($rest)+)
+    };
+
+    (@build $builder:expr, status = $status:expr, $($rest:tt)+) => {
+        $crate::err!(@build $builder.status($status), $($rest)+)
+    };
+
+    (@build $builder:expr, kind = $kind:expr, $($rest:tt)+) => {
+        $crate::err!(@build $builder.kind($kind), $($rest)+)
+    };
+
+    (@build $builder:expr, $($args:tt)+) => {
+        $builder.build(::core::format_args!($($args)+))
+    };
+
+    (@$($rest:tt)+) => {
+        $crate::err!(@build $crate::internal::ErrorBuilder::new(), @$($rest)+)
+    };
+
+    (source = $source:expr, $($args:tt)+) => {
+        $crate::internal::wrap_error_from_args($source, ::core::format_args!($($args)+))
+    };
+
+    (code = $($rest:tt)+) => {
+        $crate::err!(@build $crate::internal::ErrorBuilder::new(), code = $($rest)+)
+    };
+
+    (status = $($rest:tt)+) => {
+        $crate::err!(@build $crate::internal::ErrorBuilder::new(), status = $($rest)+)
+    };
+
+    (kind = $($rest:tt)+) => {
+        $crate::err!(@build $crate::internal::ErrorBuilder::new(), kind = $($rest)+)
+    };
+
+    ($fmt:expr) => {
+        $crate::internal::error_from_args(::core::format_args!($fmt))
+    };
+
+    ($fmt:expr, $($args:tt)*) => {
+        $crate::internal::error_from_args(::core::format_args!($fmt, $($args)*))
+    };
+}
+
+/// Construct an error on the fly, and immediately return from the current function
+///
+/// Like with [err!], the arguments can be a format string and its arguments.
+/// Any other expression is returned as the error after converting it with
+/// `Into`:
+///
+/// ```
+/// fn parse(s: &str) -> ees::Result<i32> {
+///     match s.parse() {
+///         Ok(n) if n < 0 => ees::bail!("{} is negative", n),
+///         Ok(n) => Ok(n),
+///         Err(e) => ees::bail!(e),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($fmt:literal $(,)?) => {
+        return Err(::core::convert::Into::into($crate::err!($fmt)))
+    };
+
+    ($error:expr $(,)?) => {
+        return Err(::core::convert::Into::into($error))
+    };
+
+    ($($arg:tt)*) => {
+        return Err(::core::convert::Into::into($crate::err!($($arg)*)))
+    };
+}
+
+/// Unwrap an `Option`, or return an error from the current function if it is
+/// `None`
+///
+/// The error is created from the remaining arguments, in the same way as
+/// with [bail!].
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// fn port(config: &HashMap<&str, u16>) -> ees::Result<u16> {
+///     let key = "port";
+///     let port = ees::required!(config.get(key), "missing key {}", key);
+///     Ok(*port)
+/// }
+///
+/// let e = port(&HashMap::new()).unwrap_err();
+/// assert_eq!(e.to_string(), "missing key port");
+/// ```
+#[macro_export]
+macro_rules! required {
+    ($option:expr, $($args:tt)+) => {
+        match $option {
+            ::core::option::Option::Some(value) => value,
+            ::core::option::Option::None => $crate::bail!($($args)+),
+        }
+    };
+}
+
+/// Return an error from the current function if a condition is true
+///
+/// The error is created from the remaining arguments, in the same way as
+/// with [bail!].
+///
+/// ```
+/// fn check_port(port: u16) -> ees::Result<()> {
+///     ees::bail_if!(port < 1024, "port {} is reserved", port);
+///     Ok(())
+/// }
+///
+/// assert_eq!(check_port(80).unwrap_err().to_string(), "port 80 is reserved");
+/// assert!(check_port(8080).is_ok());
+/// ```
+#[macro_export]
+macro_rules! bail_if {
+    ($condition:expr, $($args:tt)+) => {
+        if $condition {
+            $crate::bail!($($args)+);
+        }
+    };
+}
+
+/// Evaluate to a `Result<(), Error>` that is an error if a condition is true
+///
+/// Unlike [bail_if!], this doesn't return from the current function, so that
+/// checks can be combined with `?` or with other `Result` methods:
+///
+/// ```
+/// fn validate(name: &str) -> ees::Result<()> {
+///     ees::err_if!(name.is_empty(), "name is empty")?;
+///     ees::err_if!(name.len() > 8, "name {:?} is too long", name)
+/// }
+///
+/// assert_eq!(validate("").unwrap_err().to_string(), "name is empty");
+/// assert!(validate("alice").is_ok());
+/// ```
+#[macro_export]
+macro_rules! err_if {
+    ($condition:expr, $($args:tt)+) => {
+        if $condition {
+            ::core::result::Result::<(), $crate::Error>::Err(::core::convert::Into::into(
+                $crate::err!($($args)+),
+            ))
+        } else {
+            ::core::result::Result::<(), $crate::Error>::Ok(())
+        }
+    };
+}
+
+/// Evaluate several `Result`s, and return all of their values, or every error
+/// that occurred as an [ErrorList]
+///
+/// Unlike `?`, this doesn't stop at the first error, so that e.g. every
+/// invalid setting can be reported at once. Up to 8 expressions are
+/// supported.
+///
+/// ```
+/// fn parse(host: &str, port: &str, timeout: &str) -> Result<(String, u16, u64), ees::ErrorList> {
+///     let (port, timeout) = ees::try_all!(port.parse::<u16>(), timeout.parse::<u64>())?;
+///     Ok((host.to_string(), port, timeout))
+/// }
+///
+/// let errors = parse("localhost", "http", "-1").unwrap_err();
+/// assert_eq!(
+///     errors.to_string(),
+///     "2 errors occurred: invalid digit found in string; invalid digit found in string"
+/// );
+/// ```
+#[macro_export]
+macro_rules! try_all {
+    ($($result:expr),+ $(,)?) => {{
+        let mut collector = $crate::ErrorCollector::new();
+        let values = ($(collector.check($result),)+);
+        collector.ok_or_finish().map(|()| {
+            $crate::internal::AllSome::all_some(values)
+                .expect("every result is `Ok` if no errors were collected")
+        })
+    }};
+}
+
+/// Assert that the messages of an error chain are equal to a list of strings,
+/// starting with the outermost error
+///
+/// The error can be any `'static` error type, or an [Error].
+///
+/// ```
+/// let e = ees::wrap!(ees::wrap!(ees::err!("root"), "middle"), "outer");
+/// ees::assert_error_chain!(e, ["outer", "middle", "root"]);
+/// ```
+#[macro_export]
+macro_rules! assert_error_chain {
+    ($error:expr, $expected:expr $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::internal::{BoxedSource as _, ErrorSource as _};
+        let messages =
+            $crate::internal::chain_messages((&$crate::internal::SourceWrap(&$error)).ees_source());
+        let expected: &[&str] = &$expected;
+        ::core::assert_eq!(messages, expected, "unexpected error chain");
+    }};
+}
+
+/// Assert that a result is an error, and that its chain contains an error of
+/// the given type
+///
+/// ```
+/// let result: ees::Result<()> = Err(ees::wrap!(std::fmt::Error, "failed").into());
+/// ees::assert_err_matches!(result, std::fmt::Error);
+/// ```
+#[macro_export]
+macro_rules! assert_err_matches {
+    ($result:expr, $type:ty $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::internal::{BoxedSource as _, ErrorSource as _};
+        match &$result {
+            ::core::result::Result::Ok(_) => ::core::panic!(
+                "expected an error of type `{}`, but the result is `Ok`",
+                ::core::stringify!($type),
+            ),
+            ::core::result::Result::Err(error) => {
+                let error = (&$crate::internal::SourceWrap(error)).ees_source();
+                if let ::core::option::Option::Some(chain) =
+                    $crate::internal::chain_without::<$type>(error)
+                {
+                    ::core::panic!(
+                        "expected an error of type `{}` in the chain: {}",
+                        ::core::stringify!($type),
+                        chain,
+                    );
+                }
+            }
+        }
+    }};
+}
+
+/// Run an expression returning a `Result` with [retry()], repeating it as long as
+/// it fails with a transient error
+///
+/// Errors are converted with `?`, so the expression can be a block that
+/// returns early:
+///
+/// ```no_run
+/// # fn connect(_: &str) -> std::io::Result<std::net::TcpStream> { unimplemented!() }
+/// # fn handshake(_: &std::net::TcpStream) -> ees::Result<()> { Ok(()) }
+/// let policy = ees::RetryPolicy::new(3).delay(std::time::Duration::from_secs(1));
+/// let stream = ees::retry!(policy, {
+///     let stream = connect("example.com:443")?;
+///     handshake(&stream)?;
+///     stream
+/// })?;
+/// # Ok::<(), ees::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! retry {
+    ($policy:expr, $operation:expr $(,)?) => {
+        $crate::retry($policy, || -> $crate::Result<_> {
+            ::core::result::Result::Ok($operation)
+        })
+    };
+}
+
+/// Create an error from a code declared in a [CatalogEntry], with named
+/// arguments for its message template
+///
+/// ```
+/// static CATALOG: &[ees::CatalogEntry] = &[ees::CatalogEntry::new("E0007", "no such user {name}")];
+///
+/// ees::register_catalog(CATALOG);
+/// let name = "alice";
+/// let e = ees::coded_err!(E0007, name = name);
+/// assert_eq!(e.to_string(), "no such user alice");
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! coded_err {
+    ($code:ident $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::internal::coded_error(
+            ::core::stringify!($code),
+            &[$((::core::stringify!($name), &$value as &dyn ::core::fmt::Display)),*],
+        )
+    };
+}
+
+/// Defer cleanup code that returns a `Result`, so that its error is not lost
+/// if the guarded operation fails as well
+///
+/// This evaluates to a [Deferred] guard. Its [finish](Deferred::finish)
+/// method runs the cleanup and returns the error of the operation, with the
+/// cleanup error attached as a secondary error of a [CompositeError].
+///
+/// ```
+/// # fn write_data(_: &str) -> ees::Result<()> { ees::bail!("disk full") }
+/// fn save(tmp: &str) -> ees::Result<()> {
+///     let cleanup = ees::defer_err!(Err(ees::err!("failed to remove {}", tmp)));
+///     let result = write_data(tmp);
+///     cleanup.finish(result)
+/// }
+///
+/// let e = save("data.tmp").unwrap_err();
+/// assert_eq!(
+///     format!("{:#}", ees::print_error_chain(e.as_ref())),
+///     "disk full\n\nAlso failed:\n    failed to remove data.tmp"
+/// );
+/// ```
+#[macro_export]
+macro_rules! defer_err {
+    ($cleanup:expr $(,)?) => {
+        $crate::Deferred::new(|| $cleanup)
+    };
+}
+
+/// Construct an error on the fly, print it in the same way as a [MainError]
+/// and exit the process with the given exit code
+///
+/// ```no_run
+/// let path = "config.toml";
+/// ees::exit!(2, "invalid configuration file {}", path);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! exit {
+    ($code:expr, $($args:tt)+) => {
+        $crate::internal::exit_with_error($code, $crate::err!($($args)+))
+    };
+}
+
+/// Record what the program is doing, so that it can be included in error
+/// reports
+///
+/// Breadcrumbs are kept in a small buffer on the current thread, which only
+/// holds the 16 most recent ones. When a [MainError] is reported, including
+/// when it is returned from `main()`, the breadcrumbs of the reporting thread
+/// are printed after the error chain:
+///
+/// ```text
+/// Error: failed to import data
+///
+/// Caused by:
+///     invalid record on line 1043
+///
+/// Recent activity:
+///     opened data.csv
+///     parsed 1042 records
+/// ```
+///
+/// Unlike logging, recording a breadcrumb doesn't write anything, so it's
+/// cheap enough for most loops. See [breadcrumbs] for reading them.
+///
+/// ```
+/// let path = "data.csv";
+/// ees::breadcrumb!("opened {}", path);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! breadcrumb {
+    ($($args:tt)+) => {
+        $crate::internal::breadcrumb(::core::format_args!($($args)+))
+    };
+}
+
+/// Wrap an error in a new on-the-fly error
+///
+/// The source can also be an `Option` of an error. If it is `None`, the new
+/// error doesn't have a source:
+///
+/// ```
+/// let cause: Option<std::io::Error> = None;
+/// let e = ees::wrap!(cause, "failed to connect");
+/// assert!(std::error::Error::source(&e).is_none());
+/// ```
+///
+/// Named fields can be attached after a semicolon. They are rendered by the
+/// `{:#}` format, and can be retrieved with [fields]. Values are formatted with
+/// `Display`, unless they are prefixed with `?` to use `Debug` instead (`%` is
+/// also accepted for `Display`).
+///
+/// ```
+/// # let e = ees::err!("connection reset");
+/// let path = "hello.txt";
+/// let e = ees::wrap!(e, "upload failed"; path = %path, attempt = 3);
+/// assert_eq!(format!("{:#}", e), "upload failed (path = hello.txt, attempt = 3)");
+/// ```
+///
+/// A [Kind] can be given before the message, e.g. to classify errors from
+/// other crates:
+///
+/// ```
+/// # let e = std::io::Error::other("connection reset");
+/// let e = ees::wrap!(e, kind = ees::Kind::Transient, "failed to fetch {}", "index.html");
+/// assert_eq!(ees::kind_of(&e), Some(ees::Kind::Transient));
+/// ```
+#[macro_export]
+macro_rules! wrap {
+    (@args $source:expr, [$($args:tt)*] ; $($fields:tt)+) => {
+        $crate::internal::wrap_error_with_fields(
+            $crate::wrap!(@source $source),
+            ::core::format_args!($($args)*),
+            $crate::wrap!(@fields [] $($fields)+),
+        )
+    };
+
+    (@args $source:expr, [$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::wrap!(@args $source, [$($args)* $next] $($rest)*)
+    };
+
+    (@args $source:expr, [$($args:tt)*]) => {
+        $crate::internal::wrap_source_from_args(
+            $crate::wrap!(@source $source),
+            ::core::format_args!($($args)*),
+        )
+    };
+
+    (@kind $kind:expr, $source:expr, [$($args:tt)*] ; $($fields:tt)+) => {
+        $crate::internal::wrap_error_with_kind(
+            $kind,
+            $crate::wrap!(@source $source),
+            ::core::format_args!($($args)*),
+            $crate::wrap!(@fields [] $($fields)+),
+        )
+    };
+
+    (@kind $kind:expr, $source:expr, [$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::wrap!(@kind $kind, $source, [$($args)* $next] $($rest)*)
+    };
+
+    (@kind $kind:expr, $source:expr, [$($args:tt)*]) => {
+        $crate::internal::wrap_error_with_kind(
+            $kind,
+            $crate::wrap!(@source $source),
+            ::core::format_args!($($args)*),
+            [],
+        )
+    };
+
+    (@source $source:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::internal::{IntoSource as _, OptionalSource as _};
+        (&mut &mut $crate::internal::WrapSource(::core::option::Option::Some($source)))
+            .ees_into_source()
+    }};
+
+    (@fields [$($fields:expr,)*] $name:ident = % $value:expr $(, $($rest:tt)*)?) => {
+        $crate::wrap!(@fields [
+            $($fields,)*
+            $crate::internal::field_display(::core::stringify!($name), &$value),
+        ] $($($rest)*)?)
+    };
+
+    (@fields [$($fields:expr,)*] $name:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+        $crate::wrap!(@fields [
+            $($fields,)*
+            $crate::internal::field_debug(::core::stringify!($name), &$value),
+        ] $($($rest)*)?)
+    };
+
+    (@fields [$($fields:expr,)*] $name:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::wrap!(@fields [
+            $($fields,)*
+            $crate::internal::field_display(::core::stringify!($name), &$value),
+        ] $($($rest)*)?)
+    };
+
+    (@fields [$($fields:expr,)*]) => {
+        [$($fields),*]
+    };
+
+    ($source:expr, kind = $kind:expr, $($args:tt)+) => {
+        $crate::wrap!(@kind $kind, $source, [] $($args)+)
+    };
+
+    ($source:expr, $fmt:expr) => {
+        $crate::internal::wrap_source_from_args(
+            $crate::wrap!(@source $source),
+            ::core::format_args!($fmt),
+        )
+    };
+
+    ($source:expr, $($args:tt)+) => {
+        $crate::wrap!(@args $source, [] $($args)+)
+    };
+}
+
+/// Run a block, and wrap every error that is propagated from it with `?` in a
+/// new error with the given message
+///
+/// This evaluates to a [Result] with the value of the block. The message and
+/// any fields are the same as for [wrap!], and are only formatted if there is
+/// an error.
+///
+/// ```
+/// # fn read(_: &str) -> std::io::Result<String> { Ok(String::from("port = x")) }
+/// fn load(path: &str) -> ees::Result<u16> {
+///     ees::context_scope!("loading config {}", path, {
+///         let text = read(path)?;
+///         let port = text.trim_start_matches("port = ").parse::<u16>()?;
+///         port
+///     })
+/// }
+///
+/// let e = load("app.toml").unwrap_err();
+/// assert_eq!(
+///     ees::print_error_chain(e.as_ref()).to_string(),
+///     "loading config app.toml: invalid digit found in string"
+/// );
+/// ```
+///
+/// The block runs in a closure, so `return` inside of it leaves the block
+/// rather than the enclosing function.
+#[macro_export]
+macro_rules! context_scope {
+    (@scope [$($args:tt)*] , $body:block) => {
+        match (|| -> $crate::Result<_> { ::core::result::Result::Ok($body) })() {
+            ::core::result::Result::Ok(value) => ::core::result::Result::Ok(value),
+            ::core::result::Result::Err(error) => {
+                ::core::result::Result::Err($crate::Error::from($crate::wrap!(error, $($args)*)))
+            }
+        }
+    };
+
+    (@scope [$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::context_scope!(@scope [$($args)* $next] $($rest)*)
+    };
+
+    ($($args:tt)+) => {
+        $crate::context_scope!(@scope [] $($args)+)
+    };
+}
+
+/// Wrap an error in a new error, whose message is only computed when it is
+/// displayed
+///
+/// The closure can return any `Display` type, and is called each time the
+/// error is printed. This avoids formatting a message that is never shown.
+///
+/// ```
+/// # let e = ees::err!("connection reset");
+/// let id = 42;
+/// let e = ees::wrap_with!(e, move || format!("failed to fetch item {}", id));
+/// assert_eq!(
+///     ees::print_error_chain(&e).to_string(),
+///     "failed to fetch item 42: connection reset"
+/// );
+/// ```
+#[macro_export]
+macro_rules! wrap_with {
+    ($source:expr, $message:expr $(,)?) => {
+        $crate::internal::lazy_wrap_error($source, $message)
+    };
+}
+
+/// Construct an error on the fly, for use with [LocalError]
+///
+/// This is equivalent to [err!], since the constructed error is always `Send`
+/// and `Sync`, and is provided for symmetry with [local_wrap!].
+#[macro_export]
+macro_rules! local_err {
+    ($($args:tt)*) => {
+        $crate::err!($($args)*)
+    };
+}
+
+/// Wrap an error that is not necessarily `Send` or `Sync` in a new on-the-fly
+/// error
+///
+/// The resulting error can be converted into a [LocalError].
+///
+/// ```
+/// use std::{error, fmt, rc::Rc};
+///
+/// #[derive(Debug)]
+/// struct ParseError(Rc<str>);
+///
+/// impl fmt::Display for ParseError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "unexpected token {}", self.0)
+///     }
+/// }
+///
+/// impl error::Error for ParseError {}
+///
+/// fn parse() -> ees::LocalResult<()> {
+///     let e = ParseError(Rc::from("}"));
+///     Err(ees::local_wrap!(e, "failed to parse {}", "config.toml"))?
+/// }
+///
+/// assert_eq!(
+///     ees::print_error_chain(parse().unwrap_err().as_ref()).to_string(),
+///     "failed to parse config.toml: unexpected token }"
+/// );
+/// ```
+#[macro_export]
+macro_rules! local_wrap {
+    ($source:expr, $($args:tt)+) => {
+        $crate::internal::local_wrap_error_from_args($source, ::core::format_args!($($args)+))
+    };
+}
+
+/// Like [wrap!], for a source that implements `Display` but not `Error`, such
+/// as the status types of many C bindings
+///
+/// The source is converted with [from_display].
+///
+/// ```
+/// #[derive(Debug)]
+/// struct Status(i32);
+///
+/// impl std::fmt::Display for Status {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "status {}", self.0)
+///     }
+/// }
+///
+/// let e = ees::wrap_display!(Status(-3), "calling {}", "foo_init");
+/// assert_eq!(ees::print_error_chain(&e).to_string(), "calling foo_init: status -3");
+/// ```
+#[macro_export]
+macro_rules! wrap_display {
+    ($source:expr, $($args:tt)+) => {
+        $crate::wrap!($crate::from_display($source), $($args)+)
+    };
+}
+
+/// Like [wrap!], for a source that is not `Send` or `Sync`, producing an
+/// [Error] rather than a [LocalError]
+///
+/// Since the source can't be stored, this takes a snapshot of the message of
+/// every error in its chain, like [from_boxed]. The chain prints the same as
+/// with the original source, but downcasting to the types of the source and
+/// its causes is no longer possible.
+///
+/// ```
+/// use std::{error, fmt, rc::Rc};
+///
+/// #[derive(Debug)]
+/// struct ParseError(Rc<str>);
+///
+/// impl fmt::Display for ParseError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "unexpected token {}", self.0)
+///     }
+/// }
+///
+/// impl error::Error for ParseError {}
+///
+/// fn parse() -> ees::Result<()> {
+///     let e = ParseError(Rc::from("}"));
+///     Err(ees::wrap_snapshot!(e, "failed to parse {}", "config.toml"))?
+/// }
+///
+/// assert_eq!(
+///     ees::print_error_chain(parse().unwrap_err().as_ref()).to_string(),
+///     "failed to parse config.toml: unexpected token }"
+/// );
+/// ```
+#[macro_export]
+macro_rules! wrap_snapshot {
+    ($source:expr, $($args:tt)+) => {
+        $crate::wrap!($crate::DeserializedError::snapshot(&$source), $($args)+)
+    };
+}
+
+/// Define an enum error type, without needing proc macros
+///
+/// Each variant is either a unit variant or has named fields, and is followed
+/// by its message. Fields can be referred to by name in the message. A field
+/// named `source` is returned by `Error::source`, and can be any error type,
+/// an [Error], or an `Option` of a concrete error type. `Debug` is always
+/// derived.
+///
+/// ```
+/// ees::define_error! {
+///     /// Errors that can occur while loading the configuration
+///     pub enum ConfigError {
+///         Read { path: String, source: std::io::Error } => "failed to read {path}",
+///         InvalidPort { port: u32 } => "invalid port {port}",
+///         Missing => "no configuration file found",
+///     }
+/// }
+///
+/// let e = ConfigError::InvalidPort { port: 70000 };
+/// assert_eq!(e.to_string(), "invalid port 70000");
+/// ```
+///
+/// For tuple variants or more flexible messages, see `ees::Derive` behind the
+/// `derive` feature.
+#[macro_export]
+macro_rules! define_error {
+    (@source $self:ident, $variant:ident, source $($rest:ident)*) => {
+        match $self {
+            Self::$variant { source, .. } => (&$crate::internal::SourceWrap(source)).ees_source(),
+            #[allow(unreachable_patterns)]
+            _ => ::core::option::Option::None,
+        }
+    };
+
+    (@source $self:ident, $variant:ident, $field:ident $($rest:ident)*) => {
+        $crate::define_error!(@source $self, $variant, $($rest)*)
+    };
+
+    (@source $self:ident, $variant:ident, $($rest:ident)*) => {
+        ::core::option::Option::None
+    };
+
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident $({
+                    $($(#[$field_meta:meta])* $field:ident : $ty:ty),* $(,)?
+                })? => $message:literal
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant $({ $($(#[$field_meta])* $field: $ty),* })?,
+            )+
+        }
+
+        impl ::core::fmt::Display for $name {
+            #[allow(unused_variables)]
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    $(Self::$variant $({ $($field),* })? => ::core::write!(f, $message),)+
+                }
+            }
+        }
+
+        impl ::core::error::Error for $name {
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {
+                #[allow(unused_imports)]
+                use $crate::internal::{BoxedSource as _, ErrorSource as _};
+                match self {
+                    $(Self::$variant { .. } => {
+                        $crate::define_error!(@source self, $variant, $($($field)*)?)
+                    })+
+                }
+            }
+        }
+    };
+}
+
+/// Construct an error from a message, like [err!] but without a macro
+///
+/// ```
+/// let errors: Vec<ees::Error> = ["a", "b"].iter().map(ees::error_msg).collect();
+/// assert_eq!(errors[1].to_string(), "b");
+/// ```
+#[must_use]
+#[track_caller]
+pub fn error_msg(message: impl fmt::Display) -> Error {
+    err!("{}", message).into()
+}
+
+/// Wrap an error with a message, like [wrap!] but without a macro, e.g. for
+/// generated code
+///
+/// ```
+/// let e = ees::from_parts(String::from("failed to load config"), ees::err!("not found"));
+/// assert_eq!(
+///     ees::print_error_chain(e.as_ref()).to_string(),
+///     "failed to load config: not found"
+/// );
+/// ```
+#[must_use]
+#[track_caller]
+pub fn from_parts(message: impl fmt::Display, source: impl Into<Error>) -> Error {
+    wrap!(source, "{}", message).into()
+}
+
+/// Construct an error from a `&'static str` or a `String`, without formatting
+/// or copying the message
+///
+/// This is the fast path of [err!] for messages without arguments, for code
+/// that already has its message as a string, e.g. in a hot parsing loop. The
+/// only allocation is the one for boxing the error.
+///
+/// ```
+/// let e = ees::error_cow("unexpected end of input");
+/// assert_eq!(e.to_string(), "unexpected end of input");
+/// let e = ees::error_cow(format!("unexpected {:?}", '}'));
+/// assert_eq!(e.to_string(), "unexpected '}'");
+/// ```
+#[must_use]
+#[track_caller]
+pub fn error_cow(message: impl Into<Cow<'static, str>>) -> Error {
+    internal::error_from_cow(message.into())
+}
+
+/// Wrap an error with a `&'static str` or a `String`, like [from_parts] but
+/// without formatting or copying the message
+///
+/// ```
+/// let e = ees::wrap_cow(ees::err!("not found"), "failed to load config");
+/// assert_eq!(
+///     ees::print_error_chain(e.as_ref()).to_string(),
+///     "failed to load config: not found"
+/// );
+/// ```
+#[must_use]
+#[track_caller]
+pub fn wrap_cow(source: impl Into<Error>, message: impl Into<Cow<'static, str>>) -> Error {
+    internal::wrap_error_from_cow(source, message.into())
 }
 
-/// Construct an error on the fly, and immediately return from the current function
-#[macro_export]
-macro_rules! bail {
-    ($($arg:tt)*) => {
-        return Err(::std::convert::Into::into($crate::err!($($arg)*)));
-    };
+/// Convert a value that implements `Display` but not `Error` into an error
+///
+/// Unlike [error_msg], the value is stored as is and only formatted when the
+/// error is displayed.
+///
+/// ```
+/// enum Status {
+///     NotFound,
+/// }
+///
+/// impl std::fmt::Display for Status {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "resource not found")
+///     }
+/// }
+///
+/// let e = ees::wrap!(ees::from_display(Status::NotFound), "request failed");
+/// assert_eq!(
+///     ees::print_error_chain(&e).to_string(),
+///     "request failed: resource not found"
+/// );
+/// ```
+#[must_use]
+pub fn from_display(value: impl fmt::Display + Send + Sync + 'static) -> Error {
+    Box::new(internal::DisplayError(value))
 }
 
-/// Wrap an error in a new on-the-fly error
-#[macro_export]
-macro_rules! wrap {
-    ($source:expr, $fmt:expr) => {
-        $crate::internal::wrap_error_from_args($source, ::std::format_args!($fmt))
-    };
+/// Hide the types of every error in the chain, e.g. so that a library can
+/// return errors from its dependencies without making them part of its API
+///
+/// The messages and the structure of the chain are preserved, but neither the
+/// returned error nor its sources can be downcast to the original types.
+///
+/// ```
+/// let e = ees::wrap!(std::fmt::Error, "formatting failed");
+/// let e = ees::opaque(e);
+/// assert_eq!(
+///     ees::print_error_chain(e.as_ref()).to_string(),
+///     "formatting failed: an error occurred when formatting an argument"
+/// );
+/// assert!(e.source().unwrap().downcast_ref::<std::fmt::Error>().is_none());
+/// ```
+#[must_use]
+pub fn opaque(error: impl Into<Error>) -> Error {
+    Box::new(internal::OpaqueError::new(error.into()))
+}
 
-    ($source:expr, $fmt:expr, $($args:tt)*) => {
-        $crate::internal::wrap_error_from_args($source, ::std::format_args!($fmt, $($args)*))
+/// Convert any error into an [io::Error](std::io::Error), e.g. to return it
+/// from an implementation of [Read](std::io::Read)
+///
+/// The error kind is taken from the outermost `io::Error` in the chain, and
+/// is `Other` if there is none. The complete chain remains available through
+/// [source](error::Error::source).
+///
+/// ```
+/// use std::io;
+///
+/// let e = io::Error::new(io::ErrorKind::NotFound, "no such file");
+/// let e = ees::to_io_error(ees::wrap!(e, "failed to open config.toml"));
+/// assert_eq!(e.kind(), io::ErrorKind::NotFound);
+/// assert_eq!(
+///     ees::print_error_chain(&e).to_string(),
+///     "failed to open config.toml: no such file"
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn to_io_error(error: impl Into<Error>) -> std::io::Error {
+    let error = error.into();
+    let error = match error.downcast::<std::io::Error>() {
+        Ok(error) => return *error,
+        Err(error) => error,
     };
+    let kind = chain::static_chain(error.as_ref())
+        .find_map(|error| error.downcast_ref::<std::io::Error>())
+        .map_or(std::io::ErrorKind::Other, std::io::Error::kind);
+    std::io::Error::new(kind, error)
 }
 
 /// Convert any error into a type that implements [std::error::Error]. This
 /// is mainly useful for converting [Error](crate::Error) types to `anyhow::Error`
 /// or similar.
 #[inline]
-pub fn to_err(error: impl Into<Error>) -> impl error::Error + Send + Sync + 'static {
-    internal::WrapError {
+pub fn to_err(error: impl Into<Error>) -> BoxedError {
+    BoxedError {
         inner: error.into(),
     }
 }
 
+/// An [Error] that implements [std::error::Error], as returned by [to_err]
+///
+/// `BoxedError` is a transparent wrapper: its message and sources are those
+/// of the wrapped error. The wrapped error itself can be retrieved with
+/// [get_ref](Self::get_ref), e.g. after downcasting an `anyhow::Error` to a
+/// `BoxedError`:
+///
+/// ```
+/// #[derive(Debug)]
+/// struct Timeout;
+///
+/// impl std::fmt::Display for Timeout {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "timed out")
+///     }
+/// }
+///
+/// impl std::error::Error for Timeout {}
+///
+/// let e: Box<dyn std::error::Error> = Box::new(ees::to_err(Timeout));
+/// let e = e.downcast_ref::<ees::BoxedError>().unwrap();
+/// assert!(e.get_ref().is::<Timeout>());
+/// ```
+pub struct BoxedError {
+    inner: Error,
+}
+
+impl BoxedError {
+    /// The wrapped error
+    #[must_use]
+    pub fn get_ref(&self) -> ErrorRef<'_> {
+        self.inner.as_ref()
+    }
+
+    /// Convert this back into the wrapped error
+    #[must_use]
+    pub fn into_inner(self) -> Error {
+        self.inner
+    }
+}
+
+impl fmt::Debug for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl fmt::Display for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl error::Error for BoxedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner.source()
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.inner.provide(request);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
@@ -194,20 +2751,136 @@ mod tests {
         assert_eq!(owned.to_string(), "hello world");
     }
 
+    #[cfg(feature = "std")]
     fn test_bail_main_result() -> crate::MainResult {
         crate::bail!("test bail");
     }
 
+    #[cfg(feature = "std")]
+    #[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
     #[test]
     fn test_main_result_format() {
         let e = test_bail_main_result().unwrap_err();
         assert_eq!(format!("Error: {:?}", e), "Error: test bail");
     }
 
+    #[cfg(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics"))]
+    #[test]
+    fn test_main_result_format_framed() {
+        let e = test_bail_main_result().unwrap_err();
+        let report = format!("Error: {:?}", e);
+        assert_ne!(report, "Error: test bail");
+        assert!(report.contains("test bail"));
+        assert_eq!(format!("Error: {:#?}", e), "Error: test bail");
+    }
+
     fn test_bail() -> Result<(), crate::Error> {
         crate::bail!("bailing");
     }
 
+    fn test_bail_with(n: u8) -> Result<(), crate::Error> {
+        match n {
+            0 => crate::bail!(std::io::Error::other("io error")),
+            1 => crate::bail!(@warn, "warning {}", n),
+            2 => crate::bail!(code = 2, "coded"),
+            _ => crate::bail!("bailing {}", n),
+        }
+    }
+
+    #[test]
+    fn bail_values() {
+        let e = test_bail_with(0).unwrap_err();
+        assert!(e.is::<std::io::Error>());
+        let e = test_bail_with(1).unwrap_err();
+        assert_eq!(crate::severity(e.as_ref()), crate::Severity::Warning);
+        assert_eq!(e.to_string(), "warning 1");
+        let e = test_bail_with(2).unwrap_err();
+        assert_eq!(
+            crate::error_code(e.as_ref()),
+            Some(crate::ErrorCode::Number(2))
+        );
+        assert_eq!(test_bail_with(3).unwrap_err().to_string(), "bailing 3");
+    }
+
+    #[test]
+    fn optional_source() {
+        let some = Some(crate::err!("root"));
+        let e = crate::wrap!(some, "outer");
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer: root");
+        let none: Option<crate::Error> = None;
+        let e = crate::wrap!(none, "outer {}", 1; attempt = 2);
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer 1");
+        assert_eq!(crate::fields(&e)[0].value(), "2");
+    }
+
+    #[test]
+    fn assert_macros() {
+        let e: crate::Error = crate::wrap!(std::fmt::Error, "outer").into();
+        crate::assert_error_chain!(
+            e,
+            ["outer", "an error occurred when formatting an argument"]
+        );
+        let result: crate::Result<()> = Err(e);
+        crate::assert_err_matches!(result, std::fmt::Error);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected error chain")]
+    fn assert_error_chain_fails() {
+        crate::assert_error_chain!(crate::wrap!(crate::err!("root"), "outer"), ["outer"]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected an error of type `std::fmt::Error` in the chain: outer: root"
+    )]
+    fn assert_err_matches_fails() {
+        let result: crate::Result<()> = Err(crate::wrap!(crate::err!("root"), "outer").into());
+        crate::assert_err_matches!(result, std::fmt::Error);
+    }
+
+    #[test]
+    fn error_msg() {
+        let (e, line) = (crate::error_msg(42), line!());
+        assert_eq!(e.to_string(), "42");
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+    }
+
+    #[test]
+    fn opaque() {
+        let e = crate::wrap!(crate::wrap!(std::fmt::Error, "middle"), "outer"; id = 1);
+        let expected = format!("{:+#}", crate::print_error_chain(&e));
+        let e = crate::opaque(e);
+        assert_eq!(
+            format!("{:+#}", crate::print_error_chain(e.as_ref())),
+            expected
+        );
+        assert!(crate::location(e.as_ref()).is_none());
+        assert!(!crate::chain::static_chain(e.as_ref()).any(|e| e.is::<std::fmt::Error>()));
+    }
+
+    #[test]
+    fn opaque_type_names() {
+        let e = crate::wrap!(std::io::Error::other("root"), "outer");
+        let format = crate::ChainFormat::new().type_names(true);
+        let e = crate::opaque(crate::wrap!(e, "middle"));
+        assert_eq!(
+            format.display(e.as_ref()).to_string(),
+            "middle: outer: root"
+        );
+    }
+
+    #[test]
+    fn err_with_source() {
+        let e = crate::err!(source = crate::err!("root"), "outer {}", 1);
+        let w = crate::wrap!(crate::err!("root"), "outer {}", 1);
+        assert_eq!(
+            core::any::type_name_of_val(&e),
+            core::any::type_name_of_val(&w)
+        );
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer 1: root");
+    }
+
     #[test]
     fn to_err_tests() {
         let error: crate::Error = test_bail().unwrap_err();
@@ -245,8 +2918,7 @@ mod tests {
             "unknown error 18
 
 Caused by:
-    0: unknown error 7
-    1: unknown error 7"
+    unknown error 7 (repeated 2 times)"
         );
     }
 
@@ -295,4 +2967,513 @@ Caused by:
    11: oh no"
         );
     }
+
+    #[test]
+    fn caller_location() {
+        let (e, line) = (crate::err!("root"), line!());
+        let e: crate::Error = crate::wrap!(e, "failed to do work").into();
+        let location = crate::location(e.as_ref()).unwrap();
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line + 1);
+        assert_eq!(e.to_string(), "failed to do work");
+        assert_eq!(
+            format!("{:+}", crate::print_error_chain(e.as_ref())),
+            format!(
+                "failed to do work ({0}:{1}): root ({0}:{2})",
+                file!(),
+                line + 1,
+                line
+            )
+        );
+        let io_error = std::io::Error::other("oh no");
+        assert!(crate::location(&io_error).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn exit_code() {
+        let e = test_bail_main_result().unwrap_err();
+        assert_eq!(e.exit_code(), 1);
+        let e = e.with_exit_code(2);
+        assert_eq!(e.exit_code(), 2);
+        #[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
+        assert_eq!(format!("{:?}", e), "test bail");
+        #[cfg(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics"))]
+        assert!(format!("{:?}", e).contains("test bail"));
+        assert_eq!(format!("{:#?}", e), "test bail");
+    }
+
+    #[cfg(feature = "error-ids")]
+    #[test]
+    fn error_ids() {
+        let root = crate::err!("root");
+        let id = crate::error_id(&root).unwrap();
+        assert_eq!(id.to_string().len(), 6);
+        let e = crate::wrap!(root, "outer");
+        assert_eq!(crate::error_id(&e), Some(id));
+        assert!(crate::error_id(&std::fmt::Error).is_none());
+        let e = crate::MainError::from(e);
+        assert!(e.full_report().contains(&format!("[id: {}] ", id)));
+        assert!(format!("{:?}", e).contains(&format!("[id: {}] outer", id)));
+    }
+
+    #[cfg(feature = "timestamps")]
+    #[test]
+    fn timestamps() {
+        let e = crate::MainError::from(crate::err!("failed"));
+        // this is what the standard library prints for a MainResult returned
+        // from main()
+        let report = format!("{:?}", e);
+        assert!(report.starts_with(&e.context.to_string()));
+        assert_eq!(format!("{:#?}", e), "failed");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn main_error_inner() {
+        let e = crate::MainError::from(crate::wrap!(std::fmt::Error, "render failed"));
+        assert_eq!(e.as_error().to_string(), "render failed");
+        assert!(crate::find_cause::<std::fmt::Error>(e.as_error()).is_some());
+        let e = crate::wrap!(e.into_inner(), "outer");
+        assert_eq!(
+            crate::print_error_chain(&e).to_string(),
+            "outer: render failed: an error occurred when formatting an argument"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn report_header() {
+        let e = crate::MainError::from(crate::err!(code = "E1", "oh no"));
+        assert_eq!(e.header(None), "Error[E1]: ");
+        assert_eq!(e.header(Some("myapp")), "myapp: error[E1]: ");
+        let e = crate::MainError::from(crate::err!(@warn, "careful"));
+        assert_eq!(e.header(Some("myapp")), "myapp: warning: ");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_error() {
+        let e = crate::to_io_error(crate::wrap!(crate::err!("root"), "outer"));
+        assert_eq!(e.kind(), std::io::ErrorKind::Other);
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer: root");
+        let e = std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out");
+        let e = crate::to_io_error(e);
+        assert_eq!(e.kind(), std::io::ErrorKind::TimedOut);
+        assert!(!e.get_ref().unwrap().is::<std::io::Error>());
+    }
+
+    #[cfg(ees_nightly)]
+    #[test]
+    fn provide() {
+        let (e, line) = (crate::err!("root"), line!());
+        let e = crate::SharedError::from(crate::Error::from(crate::wrap!(e, "outer")));
+        let location = core::error::request_ref::<core::panic::Location<'static>>(&e).unwrap();
+        assert_eq!(location.line(), line + 1);
+    }
+
+    #[cfg(ees_nightly)]
+    #[test]
+    fn backtrace_section() {
+        #[derive(Debug)]
+        struct WithBacktrace(std::backtrace::Backtrace);
+
+        impl std::fmt::Display for WithBacktrace {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "root")
+            }
+        }
+
+        impl std::error::Error for WithBacktrace {
+            fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+                request.provide_ref(&self.0);
+            }
+        }
+
+        let e = WithBacktrace(std::backtrace::Backtrace::force_capture());
+        let e = crate::MainError::from(crate::wrap!(e, "outer"));
+        let report = format!("{:?}", e);
+        #[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
+        assert!(report.starts_with("outer\n\nCaused by:\n    root\n\nStack backtrace:\n"));
+        assert!(report.contains("outer\n\nCaused by:\n    root\n\nStack backtrace:\n"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn broken_pipe() {
+        let e = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "broken pipe");
+        let e = crate::MainError::from(crate::wrap!(e, "failed to write output"));
+        assert_eq!(e.exit_code(), 1);
+        let e = e.quiet_on_broken_pipe();
+        assert_eq!(e.exit_code(), 0);
+        let e = crate::MainError::from(crate::err!("other")).quiet_on_broken_pipe();
+        assert_eq!(e.exit_code(), 1);
+    }
+
+    #[test]
+    fn json_chain() {
+        let e = std::io::Error::other("file \"hello\"\nnot found");
+        let e = crate::wrap!(e, "failed to open\tfile");
+        assert_eq!(
+            crate::print_error_chain_json(e).to_string(),
+            r#"[{"message":"failed to open\tfile"},{"message":"file \"hello\"\nnot found","type_name":"std::io::Error"}]"#
+        );
+    }
+
+    #[test]
+    fn borrowed_errors() {
+        #[derive(Debug)]
+        struct ParseError<'a> {
+            input: &'a str,
+            source: std::num::ParseIntError,
+        }
+
+        impl std::fmt::Display for ParseError<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "invalid number {:?}", self.input)
+            }
+        }
+
+        impl std::error::Error for ParseError<'_> {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.source)
+            }
+        }
+
+        let input = String::from("x = 1a");
+        let e = ParseError {
+            input: &input[4..],
+            source: input[4..].parse::<u32>().unwrap_err(),
+        };
+        let e: crate::BorrowedErrorRef<'_> = &e;
+        assert_eq!(crate::chain(e).count(), 2);
+        assert_eq!(
+            crate::root_cause(e).to_string(),
+            "invalid digit found in string"
+        );
+        assert_eq!(
+            crate::print_error_chain(e).to_string(),
+            "invalid number \"1a\": invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn conditional_macros() {
+        fn check(n: i32) -> crate::Result<i32> {
+            crate::bail_if!(n < 0, "{} is negative", n);
+            crate::err_if!(n == 0, code = "E1", "zero")?;
+            Ok(n)
+        }
+
+        assert_eq!(check(-1).unwrap_err().to_string(), "-1 is negative");
+        let e = check(0).unwrap_err();
+        assert_eq!(
+            crate::error_code(e.as_ref()),
+            Some(crate::ErrorCode::Name("E1"))
+        );
+        assert_eq!(check(1).unwrap(), 1);
+    }
+
+    #[test]
+    fn context_scope() {
+        fn parse(a: &str, b: &str) -> crate::Result<u32> {
+            crate::context_scope!("parsing {} and {}", a, b; count = 2, {
+                let a: u32 = a.parse()?;
+                let b: u32 = b.parse()?;
+                a + b
+            })
+        }
+
+        assert_eq!(parse("1", "2").unwrap(), 3);
+        let e = parse("1", "x").unwrap_err();
+        assert_eq!(
+            crate::chain_messages(e.as_ref()),
+            ["parsing 1 and x", "invalid digit found in string"]
+        );
+        assert_eq!(format!("{:#}", e), "parsing 1 and x (count = 2)");
+    }
+
+    #[test]
+    fn chain_digest() {
+        let digest = |messages: &[&str]| {
+            crate::chain_digest(crate::DeserializedError::from_messages(messages.to_vec()).unwrap())
+        };
+        assert_eq!(digest(&["a", "b"]), digest(&["a", "b"]));
+        assert_ne!(digest(&["ab", "c"]), digest(&["a", "bc"]));
+        assert_ne!(digest(&["a"]), digest(&["a", ""]));
+    }
+
+    #[test]
+    fn from_parts() {
+        let (e, line) = (crate::from_parts("outer", crate::err!("root")), line!());
+        assert_eq!(crate::chain_messages(e.as_ref()), ["outer", "root"]);
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+    }
+
+    #[test]
+    fn cow_messages() {
+        let (e, line) = (crate::error_cow(String::from("root")), line!());
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        let (e, line) = (crate::wrap_cow(e, "outer"), line!());
+        assert_eq!(crate::chain_messages(e.as_ref()), ["outer", "root"]);
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        let e = crate::wrap_cow(std::fmt::Error, alloc::borrow::Cow::Borrowed("formatting"));
+        assert_eq!(
+            crate::ChainFormat::new()
+                .type_names(true)
+                .display(e.as_ref())
+                .to_string(),
+            "formatting: an error occurred when formatting an argument (core::fmt::Error)"
+        );
+    }
+
+    #[test]
+    fn wrap_display() {
+        let e = crate::wrap_display!(-3, kind = crate::Kind::Internal, "calling foo");
+        assert_eq!(crate::chain_messages(&e), ["calling foo", "-3"]);
+        assert_eq!(crate::kind_of(&e), Some(crate::Kind::Internal));
+    }
+
+    #[test]
+    fn wrap_snapshot() {
+        #[derive(Debug)]
+        struct NotSend(alloc::rc::Rc<str>);
+
+        impl core::fmt::Display for NotSend {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl core::error::Error for NotSend {}
+
+        let source = crate::local_wrap!(NotSend("root".into()), "inner");
+        let (e, line) = (crate::wrap_snapshot!(source, "outer"; n = 2), line!());
+        let e: crate::Error = e.into();
+        assert_eq!(
+            crate::chain_messages(e.as_ref()),
+            ["outer", "inner", "root"]
+        );
+        assert_eq!(format!("{:#}", e), "outer (n = 2)");
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+    }
+
+    #[test]
+    fn owned_chain() {
+        let e: crate::Error = crate::wrap!(crate::err!("root"), "outer").into();
+        assert_eq!(
+            format!("{:#}", crate::print_owned_chain(&e)),
+            "outer\n\nCaused by:\n    root"
+        );
+        assert_eq!(crate::print_owned_chain(&e).len(), 2);
+    }
+
+    #[test]
+    fn logfmt() {
+        let e = crate::err!("root \"cause\"");
+        assert_eq!(crate::logfmt(&e).to_string(), r#"error="root \"cause\"""#);
+        let e = crate::wrap!(crate::wrap!(crate::wrap!(e, "b: c"), "a"), "top");
+        assert_eq!(
+            crate::logfmt(&e).to_string(),
+            r#"error="top" cause0="a" cause1="b: c" root="root \"cause\"""#
+        );
+    }
+
+    #[test]
+    fn deserialized_error() {
+        assert!(crate::DeserializedError::from_messages(Vec::<String>::new()).is_none());
+        let e = crate::DeserializedError::from_messages(vec!["outer", "middle", "root"]).unwrap();
+        assert_eq!(e.message(), "outer");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer
+
+Caused by:
+    0: middle
+    1: root"
+        );
+        assert_eq!(
+            crate::print_error_chain(e).to_string(),
+            "outer: middle: root"
+        );
+    }
+
+    #[test]
+    fn wrap_fields() {
+        let path = "hello.txt";
+        let attempt = 3;
+        let e = crate::err!("connection reset");
+        let e = crate::wrap!(e, "upload {} failed", "1"; path = %path, attempt = attempt);
+        let e: crate::Error = crate::wrap!(e, "sync failed"; name = ?"docs").into();
+        assert_eq!(
+            crate::fields(e.as_ref()),
+            &[crate::internal::field_display("name", &"\"docs\"")]
+        );
+        let inner = crate::fields(e.source().unwrap());
+        assert_eq!(inner.len(), 2);
+        assert_eq!(inner[0].name(), "path");
+        assert_eq!(inner[0].value(), "hello.txt");
+        assert_eq!(inner[1].to_string(), "attempt = 3");
+        assert_eq!(
+            crate::print_error_chain(e.as_ref()).to_string(),
+            "sync failed: upload 1 failed: connection reset"
+        );
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(e.as_ref())),
+            r#"sync failed (name = "docs")
+
+Caused by:
+    0: upload 1 failed (path = hello.txt, attempt = 3)
+    1: connection reset"#
+        );
+    }
+
+    #[test]
+    fn from_boxed() {
+        use std::rc::Rc;
+
+        let message: Rc<str> = Rc::from("not send");
+        let e = crate::local_wrap!(crate::err!("root"), "{}", message);
+        let e: crate::Error = crate::from_boxed(Box::new(e));
+        assert_eq!(
+            crate::print_error_chain(e.as_ref()).to_string(),
+            "not send: root"
+        );
+    }
+
+    crate::define_error! {
+        enum DefinedError {
+            Read { path: &'static str, source: std::io::Error } => "failed to read {path}",
+            Wrapped { source: crate::Error } => "wrapped",
+            Missing => "missing",
+        }
+    }
+
+    #[test]
+    fn define_error() {
+        let io = std::io::Error::other("denied");
+        let e = DefinedError::Read {
+            path: "a.txt",
+            source: io,
+        };
+        assert_eq!(
+            crate::print_error_chain(&e).to_string(),
+            "failed to read a.txt: denied"
+        );
+        let e = DefinedError::Wrapped {
+            source: crate::err!("root").into(),
+        };
+        assert_eq!(crate::print_error_chain(&e).to_string(), "wrapped: root");
+        let e = DefinedError::Missing;
+        assert!(std::error::Error::source(&e).is_none());
+        assert_eq!(e.to_string(), "missing");
+    }
+
+    #[test]
+    fn wrap_with() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let e = crate::wrap_with!(crate::err!("root"), move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            "lazy"
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(crate::print_error_chain(&e).to_string(), "lazy: root");
+        // once to check that the message isn't empty, once to print it
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "lazy\n\nCaused by:\n    root"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert!(crate::location(&e).is_some());
+    }
+
+    #[test]
+    fn wrap_with_empty_message() {
+        let e = crate::wrap!(crate::wrap_with!(crate::err!("root"), || ""), "outer");
+        assert_eq!(crate::print_error_chain(&e).to_string(), "outer: root");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer\n\nCaused by:\n    root"
+        );
+    }
+
+    #[test]
+    fn write_error_chain() {
+        use crate::ChainStyle;
+
+        let e = crate::wrap!(crate::err!("root"), "outer");
+        let mut buffer = String::new();
+        crate::write_error_chain(&mut buffer, &e, ChainStyle::Compact).unwrap();
+        assert_eq!(buffer, "outer: root");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_error_chain_io() {
+        let e = crate::wrap!(crate::err!("root"), "outer");
+        let mut bytes = Vec::new();
+        crate::write_error_chain_io(&mut bytes, &e, crate::ChainStyle::CausedBy).unwrap();
+        assert_eq!(bytes, b"outer\n\nCaused by:\n    root");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn severity() {
+        use crate::Severity;
+
+        let e = crate::MainError::from(crate::err!(@warn, "low disk space"));
+        assert_eq!(e.severity(), Severity::Warning);
+        assert_eq!(e.exit_code(), 0);
+        let e = crate::wrap!(
+            crate::err!(@fatal, "corrupted {}", "index"),
+            "startup failed"
+        );
+        let e = crate::MainError::from(e);
+        assert_eq!(e.severity(), Severity::Fatal);
+        assert_eq!(e.exit_code(), 1);
+        assert_eq!(
+            e.to_string(),
+            "startup failed\n\nCaused by:\n    corrupted index"
+        );
+        assert_eq!(crate::severity(&crate::err!("plain")), Severity::Error);
+    }
+
+    #[test]
+    fn error_code() {
+        use crate::ErrorCode;
+
+        let e = crate::err!(code = "E0001", "root {}", 1);
+        let e = crate::wrap!(e, "outer");
+        assert_eq!(crate::error_code(&e), Some(ErrorCode::Name("E0001")));
+        let e = crate::err!(code = 404, @warn, "not found");
+        assert_eq!(crate::error_code(&e), Some(ErrorCode::Number(404)));
+        assert_eq!(crate::severity(&e), crate::Severity::Warning);
+        assert_eq!(crate::error_code(&crate::err!("plain")), None);
+    }
+
+    #[test]
+    fn kinds() {
+        use crate::Kind;
+
+        let e = crate::err!(kind = Kind::User, "invalid name {:?}", "");
+        assert_eq!(crate::kind_of(&e), Some(Kind::User));
+        let e = crate::wrap!(e, kind = Kind::Internal, "failed"; attempt = 2);
+        assert_eq!(crate::kind_of(&e), Some(Kind::Internal));
+        assert_eq!(format!("{:#}", e), "failed (attempt = 2)");
+        let e = crate::wrap!(e, "outer");
+        assert_eq!(crate::kind_of(&e), Some(Kind::Internal));
+        assert_eq!(crate::kind_of(&crate::err!(@warn, "plain")), None);
+
+        let e = crate::err!(kind = Kind::NotFound, "no such user");
+        assert_eq!(crate::HttpError::from(e).status(), 404);
+        let e = crate::err!(status = 410, kind = Kind::NotFound, "gone");
+        assert_eq!(crate::HttpError::from(e).status(), 410);
+    }
 }
@@ -0,0 +1,384 @@
+use alloc::{vec, vec::Vec};
+use core::{error, fmt, iter, slice};
+
+/// A collection of errors, e.g. from a batch job where every failed item
+/// should be reported rather than just the first one
+///
+/// ```
+/// let results = vec![Ok(1), Err(ees::err!("invalid item 2")), Err(ees::err!("invalid item 3"))];
+/// let errors: ees::ErrorList = results.into_iter().filter_map(Result::err).collect();
+/// assert_eq!(errors.to_string(), "2 errors occurred: invalid item 2; invalid item 3");
+/// ```
+///
+/// The alternate format (`{:#}`) prints each error on its own line.
+#[derive(Debug, Default)]
+pub struct ErrorList {
+    errors: Vec<crate::Error>,
+}
+
+impl ErrorList {
+    /// Create an empty list
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an error to the list
+    pub fn push(&mut self, error: impl Into<crate::Error>) {
+        self.errors.push(error.into());
+    }
+
+    /// The number of errors in the list
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `true` if the list contains no errors
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Iterate over the errors in the list
+    pub fn iter(&self) -> ErrorListIter<'_> {
+        ErrorListIter {
+            inner: self.errors.iter(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.len() {
+            0 => write!(f, "no errors occurred")?,
+            1 => write!(f, "1 error occurred")?,
+            n => write!(f, "{} errors occurred", n)?,
+        }
+        if f.alternate() {
+            write!(f, ":")?;
+            for error in &self.errors {
+                write!(f, "\n  - {}", crate::print_error_chain(error.as_ref()))?;
+            }
+        } else {
+            for (i, error) in self.errors.iter().enumerate() {
+                let separator = if i == 0 { ": " } else { "; " };
+                write!(
+                    f,
+                    "{}{}",
+                    separator,
+                    crate::print_error_chain(error.as_ref())
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ErrorList {}
+
+impl<E: Into<crate::Error>> iter::FromIterator<E> for ErrorList {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        Self {
+            errors: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<E: Into<crate::Error>> Extend<E> for ErrorList {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.errors.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl IntoIterator for ErrorList {
+    type Item = crate::Error;
+    type IntoIter = vec::IntoIter<crate::Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ErrorList {
+    type Item = crate::ErrorRef<'a>;
+    type IntoIter = ErrorListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the errors in an [ErrorList]
+#[derive(Debug, Clone)]
+pub struct ErrorListIter<'a> {
+    inner: slice::Iter<'a, crate::Error>,
+}
+
+impl<'a> Iterator for ErrorListIter<'a> {
+    type Item = crate::ErrorRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|error| error.as_ref() as _)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ErrorListIter<'_> {}
+
+/// Combine two independent errors, e.g. from two operations that ran
+/// concurrently and both failed
+///
+/// The errors are available with [ErrorList::iter]. If either error already
+/// is an [ErrorList], its errors are added individually, so that joining
+/// repeatedly produces a flat list.
+///
+/// ```
+/// let e = ees::join_errors(ees::err!("primary timed out"), ees::err!("replica timed out"));
+/// assert_eq!(e.to_string(), "2 errors occurred: primary timed out; replica timed out");
+/// assert_eq!(e.iter().nth(1).unwrap().to_string(), "replica timed out");
+/// ```
+pub fn join_errors(a: impl Into<crate::Error>, b: impl Into<crate::Error>) -> ErrorList {
+    let mut errors = ErrorList::new();
+    for error in [a.into(), b.into()] {
+        match error.downcast::<ErrorList>() {
+            Ok(list) => errors.extend(list.errors),
+            Err(error) => errors.errors.push(error),
+        }
+    }
+    errors
+}
+
+/// Accumulates errors in imperative loops, where collecting into an
+/// [ErrorList] from an iterator is awkward
+///
+/// ```
+/// fn validate(items: &[i32]) -> Result<(), ees::ErrorList> {
+///     let mut collector = ees::ErrorCollector::new();
+///     for item in items {
+///         if *item < 0 {
+///             collector.push(ees::err!("invalid item {}", item));
+///         }
+///     }
+///     collector.ok_or_finish()
+/// }
+///
+/// assert!(validate(&[1, 2]).is_ok());
+/// assert_eq!(validate(&[-1, 2, -3]).unwrap_err().len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct ErrorCollector {
+    errors: ErrorList,
+}
+
+impl ErrorCollector {
+    /// Create an empty collector
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error
+    pub fn push(&mut self, error: impl Into<crate::Error>) {
+        self.errors.push(error);
+    }
+
+    /// Record the error of a result, if any, and return its value otherwise
+    pub fn check<T, E: Into<crate::Error>>(&mut self, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.push(error);
+                None
+            }
+        }
+    }
+
+    /// The number of errors recorded so far
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `true` if no errors have been recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns `Ok(())` if no errors were recorded, or all recorded errors
+    /// otherwise
+    pub fn ok_or_finish(self) -> Result<(), ErrorList> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// Extension methods for iterators of results, e.g. from processing a batch
+/// of items
+pub trait ResultIteratorExt<T, E>: Iterator<Item = Result<T, E>> + Sized {
+    /// Separate the values from the errors, consuming the whole iterator
+    ///
+    /// ```
+    /// use ees::ResultIteratorExt;
+    ///
+    /// let (ports, errors) = ["80", "x", "443", "-1"].iter().map(|s| s.parse::<u16>()).partition_errors();
+    /// assert_eq!(ports, [80, 443]);
+    /// assert_eq!(
+    ///     errors.to_string(),
+    ///     "2 errors occurred: invalid digit found in string; invalid digit found in string"
+    /// );
+    /// ```
+    fn partition_errors(self) -> (Vec<T>, ErrorList);
+
+    /// Collect the values if there are no errors, or otherwise the first `n`
+    /// errors
+    ///
+    /// The iterator is not advanced after the `n`th error, so the remaining
+    /// items aren't processed. If `n` is zero, the first error is returned.
+    ///
+    /// ```
+    /// use ees::ResultIteratorExt;
+    ///
+    /// let items = vec![Err(ees::err!("bad 1")), Ok(2), Err(ees::err!("bad 3")), Err(ees::err!("bad 4"))];
+    /// let errors = items.into_iter().collect_or_first_n_errors(2).unwrap_err();
+    /// assert_eq!(errors.to_string(), "2 errors occurred: bad 1; bad 3");
+    /// ```
+    fn collect_or_first_n_errors(self, n: usize) -> Result<Vec<T>, ErrorList>;
+}
+
+impl<T, E: Into<crate::Error>, I: Iterator<Item = Result<T, E>>> ResultIteratorExt<T, E> for I {
+    fn partition_errors(self) -> (Vec<T>, ErrorList) {
+        let mut values = Vec::new();
+        let mut errors = ErrorList::new();
+        for result in self {
+            match result {
+                Ok(value) => values.push(value),
+                Err(error) => errors.push(error),
+            }
+        }
+        (values, errors)
+    }
+
+    fn collect_or_first_n_errors(self, n: usize) -> Result<Vec<T>, ErrorList> {
+        let mut values = Vec::new();
+        let mut errors = ErrorList::new();
+        for result in self {
+            match result {
+                Ok(value) if errors.is_empty() => values.push(value),
+                // the values are discarded anyway
+                Ok(_) => (),
+                Err(error) => {
+                    errors.push(error);
+                    if errors.len() >= n.max(1) {
+                        break;
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn try_all() {
+        let values = crate::try_all!(Ok::<_, crate::Error>(1), "2".parse::<u8>());
+        assert_eq!(values.unwrap(), (1, 2));
+        let errors = crate::try_all!(
+            "x".parse::<u8>(),
+            Ok::<_, crate::Error>("ok"),
+            Err::<(), _>(crate::err!("invalid port")),
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors.to_string(),
+            "2 errors occurred: invalid digit found in string; invalid port"
+        );
+    }
+
+    #[test]
+    fn error_list() {
+        let mut errors = crate::ErrorList::new();
+        assert!(errors.is_empty());
+        assert_eq!(errors.to_string(), "no errors occurred");
+        errors.push(crate::wrap!(crate::err!("not found"), "item 1 failed"));
+        assert_eq!(
+            errors.to_string(),
+            "1 error occurred: item 1 failed: not found"
+        );
+        errors.extend(vec![crate::err!("item 2 failed")]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            format!("{:#}", errors),
+            "2 errors occurred:
+  - item 1 failed: not found
+  - item 2 failed"
+        );
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, ["item 1 failed", "item 2 failed"]);
+    }
+
+    #[test]
+    fn join_errors() {
+        let e = super::join_errors(crate::err!("a"), crate::wrap!(crate::err!("b"), "c"));
+        let e = super::join_errors(e, crate::err!("d"));
+        assert_eq!(e.len(), 3);
+        assert_eq!(e.to_string(), "3 errors occurred: a; c: b; d");
+        let messages: Vec<String> = e.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, ["a", "c", "d"]);
+    }
+
+    #[test]
+    fn result_iterator() {
+        use crate::ResultIteratorExt;
+
+        let (values, errors) = vec![Ok(1), Err(crate::err!("a")), Ok(3)]
+            .into_iter()
+            .partition_errors();
+        assert_eq!(values, [1, 3]);
+        assert_eq!(errors.to_string(), "1 error occurred: a");
+
+        let mut processed = 0;
+        let results = (0..10).map(|i| {
+            processed += 1;
+            if i % 2 == 1 {
+                Err(crate::err!("item {}", i))
+            } else {
+                Ok(i)
+            }
+        });
+        let errors = results.collect_or_first_n_errors(0).unwrap_err();
+        assert_eq!(errors.to_string(), "1 error occurred: item 1");
+        assert_eq!(processed, 2);
+        let values = (0..3)
+            .map(Ok::<_, crate::Error>)
+            .collect_or_first_n_errors(1);
+        assert_eq!(values.unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn error_collector() {
+        let mut collector = crate::ErrorCollector::new();
+        assert_eq!(collector.check(Ok::<_, crate::Error>(5)), Some(5));
+        assert_eq!(collector.check(Err::<i32, _>(crate::err!("failed"))), None);
+        collector.push(crate::err!("failed again"));
+        assert_eq!(collector.len(), 2);
+        assert_eq!(
+            collector.ok_or_finish().unwrap_err().to_string(),
+            "2 errors occurred: failed; failed again"
+        );
+        assert!(crate::ErrorCollector::new().ok_or_finish().is_ok());
+    }
+}
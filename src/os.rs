@@ -0,0 +1,50 @@
+//! Helpers for errors reported by the operating system, e.g. through `errno`
+//! on Unix or `GetLastError` on Windows
+
+use std::{fmt, io};
+
+/// Wrap the error of the last OS call on this thread in a new error
+///
+/// This should be called immediately after the failing call, before anything
+/// else can overwrite the error code.
+///
+/// ```no_run
+/// # extern "C" { fn chdir(path: *const std::os::raw::c_char) -> i32; }
+/// fn change_dir(path: &std::ffi::CStr) -> ees::Result<()> {
+///     if unsafe { chdir(path.as_ptr()) } != 0 {
+///         return Err(ees::os::last_os_error(format_args!("failed to enter {:?}", path)));
+///     }
+///     Ok(())
+/// }
+/// ```
+#[track_caller]
+pub fn last_os_error(context: impl fmt::Display) -> crate::Error {
+    crate::wrap!(io::Error::last_os_error(), "{}", context).into()
+}
+
+/// Returns the raw OS error code of the outermost [io::Error] in the chain
+/// that has one
+///
+/// ```
+/// let e = std::io::Error::from_raw_os_error(2);
+/// let e = ees::wrap!(e, "failed to open file");
+/// assert_eq!(ees::os::raw_os_error(&e), Some(2));
+/// ```
+#[must_use]
+pub fn raw_os_error(error: crate::ErrorRef<'_>) -> Option<i32> {
+    crate::chain::static_chain(error)
+        .filter_map(|error| error.downcast_ref::<io::Error>())
+        .find_map(io::Error::raw_os_error)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn os_errors() {
+        let e = super::last_os_error("context");
+        assert_eq!(e.to_string(), "context");
+        assert!(e.source().unwrap().is::<std::io::Error>());
+        let e = crate::wrap!(crate::err!("not an OS error"), "outer");
+        assert_eq!(super::raw_os_error(&e), None);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{any::Any, backtrace, error, fmt, panic};
+
+/// Replace the default panic message with a report in the same style as
+/// [MainError](crate::MainError)
+///
+/// ```text
+/// Panic: index out of bounds: the len is 3 but the index is 7
+///
+/// Location:
+///     src/main.rs:12:5
+/// ```
+///
+/// A backtrace is appended if it is enabled through the `RUST_BACKTRACE` or
+/// `RUST_LIB_BACKTRACE` environment variables. This replaces any previously
+/// installed panic hook.
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        report_panic(info, |report| eprintln!("{}", report))
+    }));
+}
+
+/// Pass the report for a panic to `write`, e.g. to print it to stderr
+pub(crate) fn report_panic(info: &panic::PanicHookInfo<'_>, write: impl FnOnce(&dyn fmt::Display)) {
+    let backtrace = backtrace::Backtrace::capture();
+    let report = PanicReport {
+        message: payload_message(info.payload()),
+        location: info.location(),
+        backtrace: Some(&backtrace)
+            .filter(|backtrace| backtrace.status() == backtrace::BacktraceStatus::Captured),
+    };
+    write(&report);
+}
+
+/// Run a function, converting a panic into an error
+///
+/// If the panic payload is an [Error](crate::Error), e.g. from
+/// `std::panic::panic_any`, it is returned as is. Otherwise the error has the
+/// message `panicked: ...`. The panic is still reported by the panic hook
+/// before it is caught.
+///
+/// The function is treated as [UnwindSafe](panic::UnwindSafe), so any state it
+/// shares with the caller may be left inconsistent after a panic.
+///
+/// ```
+/// let result: ees::Result<()> = ees::catch_panic(|| panic!("plugin crashed"));
+/// assert_eq!(result.unwrap_err().to_string(), "panicked: plugin crashed");
+/// ```
+pub fn catch_panic<T>(f: impl FnOnce() -> crate::Result<T>) -> crate::Result<T> {
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => match payload.downcast::<crate::Error>() {
+            Ok(error) => Err(*error),
+            Err(payload) => Err(Box::new(PanicError {
+                message: payload_message(&*payload).to_string(),
+            })),
+        },
+    }
+}
+
+#[derive(Debug)]
+struct PanicError {
+    message: String,
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.message)
+    }
+}
+
+impl error::Error for PanicError {}
+
+/// The message of a panic payload, which is usually a `&str` or a `String`
+pub(crate) fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+struct PanicReport<'a> {
+    message: &'a str,
+    location: Option<&'a panic::Location<'a>>,
+    backtrace: Option<&'a backtrace::Backtrace>,
+}
+
+impl fmt::Display for PanicReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "color")]
+        {
+            if crate::color::enabled() {
+                write!(f, "Panic: \x1b[1;31m{}\x1b[0m", self.message)?;
+            } else {
+                write!(f, "Panic: {}", self.message)?;
+            }
+        }
+        #[cfg(not(feature = "color"))]
+        write!(f, "Panic: {}", self.message)?;
+        if let Some(location) = self.location {
+            write!(f, "\n\nLocation:\n    {}", location)?;
+        }
+        if let Some(backtrace) = self.backtrace {
+            write!(f, "\n\nBacktrace:\n{}", backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn panic_report() {
+        let payload: Box<dyn std::any::Any + Send> = Box::new(String::from("oh no"));
+        let location = std::panic::Location::caller();
+        let report = super::PanicReport {
+            message: super::payload_message(&*payload),
+            location: Some(location),
+            backtrace: None,
+        };
+        assert_eq!(
+            report.to_string(),
+            format!("Panic: oh no\n\nLocation:\n    {}", location)
+        );
+        assert_eq!(super::payload_message(&7), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn catch_panic() {
+        let result = super::catch_panic(|| Ok(1));
+        assert_eq!(result.unwrap(), 1);
+        let result: crate::Result<()> = super::catch_panic(|| panic!("index {} out of bounds", 7));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "panicked: index 7 out of bounds"
+        );
+        let result: crate::Result<()> = super::catch_panic(|| {
+            let error: crate::Error = crate::err!("custom payload").into();
+            std::panic::panic_any(error)
+        });
+        assert_eq!(result.unwrap_err().to_string(), "custom payload");
+    }
+}
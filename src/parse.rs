@@ -0,0 +1,118 @@
+use core::{convert::TryFrom, fmt, str::FromStr};
+
+/// Inputs longer than this are truncated in error messages
+const MAX_INPUT_CHARS: usize = 32;
+
+/// The input that failed to parse, quoted and escaped, and truncated if it
+/// is too long
+struct Input<'a>(&'a str);
+
+impl fmt::Display for Input<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.char_indices().nth(MAX_INPUT_CHARS) {
+            Some((end, _)) => write!(f, "{:?}...", &self.0[..end]),
+            None => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+/// Parse a string, adding the input to the error
+///
+/// ```
+/// let port: ees::Result<u16> = ees::parse("80a");
+/// assert_eq!(
+///     ees::print_error_chain(port.unwrap_err().as_ref()).to_string(),
+///     "invalid value \"80a\": invalid digit found in string"
+/// );
+/// ```
+///
+/// The input is escaped, and truncated after 32 characters.
+#[track_caller]
+pub fn parse<T>(input: &str) -> crate::Result<T>
+where
+    T: FromStr,
+    T::Err: Into<crate::Error>,
+{
+    match input.parse() {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(error, "invalid value {}", Input(input)).into()),
+    }
+}
+
+/// Like [parse], with a label for what the input is, e.g. `"port number"`
+///
+/// ```
+/// let port: ees::Result<u16> = ees::parse_ctx("99999", "port number");
+/// assert_eq!(
+///     ees::print_error_chain(port.unwrap_err().as_ref()).to_string(),
+///     "invalid port number \"99999\": number too large to fit in target type"
+/// );
+/// ```
+#[track_caller]
+pub fn parse_ctx<T>(input: &str, label: impl fmt::Display) -> crate::Result<T>
+where
+    T: FromStr,
+    T::Err: Into<crate::Error>,
+{
+    match input.parse() {
+        Ok(value) => Ok(value),
+        Err(error) => Err(crate::wrap!(error, "invalid {} {}", label, Input(input)).into()),
+    }
+}
+
+/// Convert a value with [TryFrom], adding the value and a label to the error
+///
+/// ```
+/// let timeout: ees::Result<u8> = ees::convert_ctx(300_i32, "timeout");
+/// assert_eq!(
+///     ees::print_error_chain(timeout.unwrap_err().as_ref()).to_string(),
+///     "invalid timeout 300: out of range integral type conversion attempted"
+/// );
+/// ```
+#[track_caller]
+pub fn convert_ctx<T, U>(value: U, label: impl fmt::Display) -> crate::Result<T>
+where
+    T: TryFrom<U>,
+    T::Error: Into<crate::Error>,
+    U: fmt::Debug,
+{
+    // the value is formatted before it is moved into the conversion
+    let mut input = alloc::format!("{:?}", value);
+    match T::try_from(value) {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            if let Some((end, _)) = input.char_indices().nth(MAX_INPUT_CHARS) {
+                input.truncate(end);
+                input.push_str("...");
+            }
+            Err(crate::wrap!(error, "invalid {} {}", label, input).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse() {
+        let (e, line) = (super::parse::<i32>("").unwrap_err(), line!());
+        assert_eq!(e.to_string(), "invalid value \"\"");
+        assert_eq!(crate::location(e.as_ref()).unwrap().line(), line);
+        assert_eq!(super::parse::<i32>(" 42 ".trim()).unwrap(), 42);
+
+        let input = "\"quoted\"\nand a line break, followed by more text";
+        let e = super::parse_ctx::<f64>(input, "ratio").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid ratio \"\\\"quoted\\\"\\nand a line break, follo\"..."
+        );
+        let e = super::convert_ctx::<char, _>(0xd800_u32, "character").unwrap_err();
+        assert_eq!(e.to_string(), "invalid character 55296");
+        let e = super::convert_ctx::<u8, _>(-1000000000000000000000000000000000_i128, "byte");
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "invalid byte -1000000000000000000000000000000..."
+        );
+    }
+}
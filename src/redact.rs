@@ -0,0 +1,267 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    RwLock,
+};
+
+/// What secrets are replaced with
+const MASK: &str = "[REDACTED]";
+
+/// A value that is never printed, created with [redact]
+///
+/// Both `Display` and `Debug` write `[REDACTED]`.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Redacted<T>(T);
+
+/// Mark a value as sensitive, so that it isn't included in error messages
+///
+/// ```
+/// let token = "hunter2";
+/// let e = ees::err!("authentication failed for token {}", ees::redact(token));
+/// assert_eq!(e.to_string(), "authentication failed for token [REDACTED]");
+/// ```
+///
+/// Secrets that can't be marked where the message is formatted, e.g. because
+/// they are part of the messages of other crates, can be masked by registering
+/// a [Redactor] instead.
+pub fn redact<T>(value: T) -> Redacted<T> {
+    Redacted(value)
+}
+
+impl<T> Redacted<T> {
+    /// The sensitive value
+    #[must_use]
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the sensitive value
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(MASK)
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(MASK)
+    }
+}
+
+/// A rule for masking secrets in the output of every chain renderer, e.g.
+/// [print_error_chain](crate::print_error_chain), [ChainFormat](crate::ChainFormat),
+/// [print_error_chain_json](crate::print_error_chain_json) and
+/// [MainError](crate::MainError) reports
+///
+/// Redactors are registered with [add_redactor], and are applied to the
+/// messages after they have been formatted, in the order they were added.
+/// Secrets are replaced with `[REDACTED]`:
+///
+/// ```
+/// use ees::Redactor;
+///
+/// ees::add_redactor(Redactor::key("password"));
+/// ees::add_redactor(Redactor::prefix("ghp_"));
+///
+/// let e = ees::wrap!(ees::err!("token ghp_a1b2c3 was rejected"), "login failed");
+/// let e = ees::wrap!(e, "db_password='a b c' is invalid");
+/// assert_eq!(
+///     ees::print_error_chain(&e).to_string(),
+///     "db_password='[REDACTED]' is invalid: login failed: token [REDACTED] was rejected"
+/// );
+/// ```
+///
+/// The `Display` output of the errors themselves is left unchanged.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redactor(Rule);
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    Key(String),
+    Prefix(String),
+    Value(String),
+}
+
+#[cfg(feature = "std")]
+impl Redactor {
+    /// Mask the values of a key, e.g. `password=...`, `password: ...` or a
+    /// field named `password`
+    ///
+    /// The key is matched case-insensitively, also as the end of a longer key
+    /// such as `db_password`. A value ends at whitespace or punctuation such
+    /// as `,`, unless it is quoted.
+    #[must_use]
+    pub fn key(key: impl Into<String>) -> Self {
+        Self(Rule::Key(key.into().to_ascii_lowercase()))
+    }
+
+    /// Mask words that start with a prefix, e.g. `ghp_` for GitHub tokens
+    #[must_use]
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self(Rule::Prefix(prefix.into()))
+    }
+
+    /// Mask every occurrence of a secret, e.g. an API key loaded at startup
+    #[must_use]
+    pub fn value(secret: impl Into<String>) -> Self {
+        Self(Rule::Value(secret.into()))
+    }
+
+    fn apply(&self, text: String) -> String {
+        match &self.0 {
+            // an empty rule would match everywhere
+            Rule::Key(key) | Rule::Prefix(key) | Rule::Value(key) if key.is_empty() => text,
+            Rule::Key(key) => mask_keys(&text, key),
+            Rule::Prefix(prefix) => mask_words(&text, prefix),
+            Rule::Value(secret) => text.replace(secret.as_str(), MASK),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+static REDACTORS: RwLock<Vec<Redactor>> = RwLock::new(Vec::new());
+// checked before taking the lock, so that printing stays cheap without any
+// redactors
+#[cfg(feature = "std")]
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Register a [Redactor], in addition to the previously registered ones
+#[cfg(feature = "std")]
+pub fn add_redactor(redactor: Redactor) {
+    // the redactors are only ever appended to, so a poisoned lock holds valid
+    // data
+    let mut redactors = REDACTORS.write().unwrap_or_else(|e| e.into_inner());
+    redactors.push(redactor);
+    ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Writes a value with the secrets of the registered redactors masked
+pub(crate) struct Redacting<'a>(pub(crate) &'a dyn fmt::Display);
+
+impl fmt::Display for Redacting<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "std")]
+        if ACTIVE.load(Ordering::Relaxed) {
+            let redactors = REDACTORS.read().unwrap_or_else(|e| e.into_inner());
+            let text = redactors
+                .iter()
+                .fold(self.0.to_string(), |text, redactor| redactor.apply(text));
+            return f.write_str(&text);
+        }
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || "_-.+/=".contains(c)
+}
+
+#[cfg(feature = "std")]
+fn mask_words(text: &str, prefix: &str) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(prefix) {
+        masked.push_str(&rest[..i]);
+        let word = &rest[i..];
+        let end = word.find(|c| !is_word(c)).unwrap_or(word.len());
+        masked.push_str(MASK);
+        rest = &word[end..];
+    }
+    masked.push_str(rest);
+    masked
+}
+
+#[cfg(feature = "std")]
+fn mask_keys(text: &str, key: &str) -> String {
+    // lowercasing ASCII keeps the byte offsets the same
+    let lowercase = text.to_ascii_lowercase();
+    let mut masked = String::with_capacity(text.len());
+    let mut start = 0;
+    let mut search = 0;
+    while let Some(i) = lowercase[search..].find(key) {
+        let after_key = search + i + key.len();
+        search = after_key;
+        let value = match value_span(&text[after_key..]) {
+            Some((from, to)) => (after_key + from, after_key + to),
+            None => continue,
+        };
+        masked.push_str(&text[start..value.0]);
+        masked.push_str(MASK);
+        start = value.1;
+        search = value.1;
+    }
+    masked.push_str(&text[start..]);
+    masked
+}
+
+/// The span of the value after a key, e.g. ` = "secret"`, relative to the
+/// end of the key
+#[cfg(feature = "std")]
+fn value_span(after_key: &str) -> Option<(usize, usize)> {
+    // keys can be quoted, e.g. in JSON
+    let rest = after_key.trim_start_matches(['"', '\'']);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix(['=', ':'])?.trim_start();
+    let from = after_key.len() - rest.len();
+    let (from, len) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let len = rest[1..].find(quote).unwrap_or(rest.len() - 1);
+            (from + 1, len)
+        }
+        _ => {
+            let end = |c: char| c.is_whitespace() || ",;&)]}\"'".contains(c);
+            (from, rest.find(end).unwrap_or(rest.len()))
+        }
+    };
+    Some((from, from + len)).filter(|_| len > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString};
+
+    #[test]
+    fn redacted() {
+        let secret = super::redact("hunter2".to_string());
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert_eq!(format!("{:?}", Some(&secret)), "Some([REDACTED])");
+        assert_eq!(secret.into_inner(), "hunter2");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn redactors() {
+        let key = super::Redactor::key("Password");
+        assert_eq!(
+            key.apply("password=hunter2, DB_PASSWORD: \"a b\" (password = x)".to_string()),
+            "password=[REDACTED], DB_PASSWORD: \"[REDACTED]\" (password = [REDACTED])"
+        );
+        assert_eq!(
+            key.apply("{\"password\": \"x\", \"passwords\": []} password".to_string()),
+            "{\"password\": \"[REDACTED]\", \"passwords\": []} password"
+        );
+        assert_eq!(key.apply("password=''".to_string()), "password=''");
+
+        let prefix = super::Redactor::prefix("ghp_");
+        assert_eq!(
+            prefix.apply("tokens ghp_a1/b2=, (ghp_c3)".to_string()),
+            "tokens [REDACTED], ([REDACTED])"
+        );
+        let value = super::Redactor::value("hunter2");
+        assert_eq!(
+            value.apply("hunter2hunter2".to_string()),
+            "[REDACTED][REDACTED]"
+        );
+        assert_eq!(super::Redactor::value("").apply("text".to_string()), "text");
+    }
+}
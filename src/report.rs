@@ -0,0 +1,227 @@
+use alloc::{string::String, string::ToString, vec::Vec};
+use core::{error, fmt, iter};
+
+/// An error with additional `note:` and `help:` lines, similar to compiler
+/// diagnostics
+///
+/// `Report` is a transparent wrapper: its message and sources are those of the
+/// wrapped error. The notes and help lines of every `Report` in the chain are
+/// printed after the causes by the `{:#}` format of
+/// [print_error_chain](crate::print_error_chain), and when the error is
+/// returned from `main()` as a [MainError](crate::MainError).
+///
+/// ```
+/// let e = ees::Report::new(ees::err!("no configuration file found"))
+///     .with_note("the configuration is created on first run")
+///     .with_help("try running `app init`");
+/// assert_eq!(
+///     format!("{:#}", ees::print_error_chain(&e)),
+///     "no configuration file found\n\n\
+///      note: the configuration is created on first run\n\
+///      help: try running `app init`"
+/// );
+/// ```
+pub struct Report(View<State>);
+
+#[derive(Debug)]
+struct State {
+    error: crate::Error,
+    notes: Vec<String>,
+    help: Vec<String>,
+}
+
+impl Report {
+    /// Wrap an error, without any notes or help lines
+    ///
+    /// If the error already is a `Report`, it is returned unchanged.
+    pub fn new(error: impl Into<crate::Error>) -> Self {
+        match error.into().downcast::<Self>() {
+            Ok(report) => *report,
+            Err(error) => Self(View(State {
+                error,
+                notes: Vec::new(),
+                help: Vec::new(),
+            })),
+        }
+    }
+
+    /// Add a `note:` line, explaining the context of the error
+    #[must_use]
+    pub fn with_note(mut self, note: impl fmt::Display) -> Self {
+        self.0 .0.notes.push(note.to_string());
+        self
+    }
+
+    /// Add a `help:` line, suggesting how to fix the error
+    #[must_use]
+    pub fn with_help(mut self, help: impl fmt::Display) -> Self {
+        self.0 .0.help.push(help.to_string());
+        self
+    }
+
+    /// The `note:` lines of this report
+    #[must_use]
+    pub fn notes(&self) -> &[String] {
+        &self.0 .0.notes
+    }
+
+    /// The `help:` lines of this report
+    #[must_use]
+    pub fn help(&self) -> &[String] {
+        &self.0 .0.help
+    }
+
+    /// The wrapped error
+    #[must_use]
+    pub fn as_error(&self) -> crate::ErrorRef<'_> {
+        self.0 .0.error.as_ref()
+    }
+}
+
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = &self.0 .0;
+        f.debug_struct("Report")
+            .field("error", &state.error)
+            .field("notes", &state.notes)
+            .field("help", &state.help)
+            .finish()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0 .0.error.fmt(f)
+    }
+}
+
+impl error::Error for Report {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0 .0.error.source()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&self.0)
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.0 .0.error.provide(request);
+    }
+}
+
+/// The state of an error of this crate, which the error returns from the
+/// deprecated `Error::cause`
+///
+/// Sources can be downcast, but the outermost error of an `ErrorChain` can
+/// borrow data, so it can't. References and boxes forward `cause` though, and
+/// the view returns itself from `source`, which is always `'static`. This
+/// finds the notes of a `Report`, the code of an `err!` error and so on
+/// without formatting the errors.
+#[derive(Debug)]
+pub(crate) struct View<T>(pub(crate) T);
+
+impl<T> fmt::Display for View<T> {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug + 'static> error::Error for View<T> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self)
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        // the default would be `source`, which would never end
+        None
+    }
+}
+
+/// The state of type `T` of an error, or of an error it wraps transparently,
+/// e.g. the primary error of a [CompositeError](crate::CompositeError)
+pub(crate) fn view<T: fmt::Debug + 'static>(error: &dyn error::Error) -> Option<&T> {
+    iter::successors(Some(error), |error| transparent(*error)).find_map(own_view)
+}
+
+fn own_view<T: fmt::Debug + 'static>(error: &dyn error::Error) -> Option<&T> {
+    #[allow(deprecated)]
+    let view = error.cause()?.source()?;
+    Some(&view.downcast_ref::<View<T>>()?.0)
+}
+
+/// The error wrapped by a transparent wrapper, which shares its message and
+/// sources
+fn transparent(error: &dyn error::Error) -> Option<&dyn error::Error> {
+    if let Some(report) = own_view::<State>(error) {
+        return Some(report.error.as_ref());
+    }
+    #[cfg(feature = "fancy")]
+    if let Some(snippet) = own_view::<crate::snippet::State>(error) {
+        return Some(snippet.error.as_ref());
+    }
+    Some(own_view::<crate::composite::State>(error)?.primary.as_ref())
+}
+
+/// Every error in the chain, including those wrapped transparently
+fn errors(error: &dyn error::Error) -> impl Iterator<Item = &dyn error::Error> {
+    crate::Chain::new(error)
+        .flat_map(|error| iter::successors(Some(error), |error| transparent(*error)))
+}
+
+/// The [ErrorCode](crate::ErrorCode) of an error, which unlike
+/// [error_code](crate::error_code) doesn't require the error to be `'static`
+#[cfg(feature = "std")]
+pub(crate) fn error_code(error: &dyn error::Error) -> Option<crate::ErrorCode> {
+    view::<crate::ErrorCode>(error).copied()
+}
+
+/// The type name of the source of an error created by `wrap!`, if it has one
+pub(crate) fn source_type(error: &dyn error::Error) -> Option<&'static str> {
+    Some(view::<crate::internal::SourceType>(error)?.0())
+}
+
+/// Write the snippet of every `Snippet` in the chain, the secondary errors of
+/// every [CompositeError](crate::CompositeError), and then the notes and help
+/// lines of every [Report], each after an empty line
+pub(crate) fn write_sections(f: &mut fmt::Formatter<'_>, error: &dyn error::Error) -> fmt::Result {
+    #[cfg(feature = "fancy")]
+    for snippet in errors(error).filter_map(own_view::<crate::snippet::State>) {
+        f.write_str("\n")?;
+        snippet.write(f)?;
+    }
+    let composites = errors(error).filter_map(own_view::<crate::composite::State>);
+    let mut composites = composites.filter(|composite| !composite.secondary.is_empty());
+    if let Some(first) = composites.next() {
+        f.write_str("\n")?;
+        for composite in iter::once(first).chain(composites) {
+            composite.write_secondary(f)?;
+        }
+    }
+    let reports = || errors(error).filter_map(own_view::<State>);
+    let notes = reports().flat_map(|report| report.notes.iter().map(|note| ("note", note)));
+    let help = reports().flat_map(|report| report.help.iter().map(|help| ("help", help)));
+    let mut lines = notes.chain(help).peekable();
+    if lines.peek().is_some() {
+        f.write_str("\n")?;
+    }
+    for (label, line) in lines {
+        write!(f, "\n{}: {}", label, line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    #[test]
+    fn nested_reports() {
+        let inner = crate::Report::new(crate::err!("root")).with_note("inner note");
+        let outer = crate::Report::new(crate::wrap!(inner, "outer")).with_help("outer help");
+        let e = crate::MainError::from(outer);
+        assert_eq!(
+            e.to_string(),
+            "outer\n\nCaused by:\n    root\n\nnote: inner note\nhelp: outer help"
+        );
+    }
+}
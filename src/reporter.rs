@@ -0,0 +1,116 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::RwLock,
+};
+
+/// Forwards errors to an external service, e.g. for crash telemetry
+///
+/// Once a reporter is installed with [set_reporter], every [MainError](crate::MainError)
+/// that is reported by [run](crate::run), [exit!](crate::exit), `#[ees::main]`
+/// or its [Termination](std::process::Termination) impl is passed to
+/// [report](Self::report). A [MainResult](crate::MainResult) returned from
+/// `main()` directly is printed by the standard library, which only formats
+/// it, so it isn't reported. Reporters can also opt into seeing every error
+/// created with [wrap!](crate::wrap), which is useful for errors that are
+/// handled rather than propagated to `main()`.
+///
+/// This crate doesn't depend on any telemetry SDK, but a reporter is usually
+/// only a few lines, e.g. with the Sentry SDK:
+///
+/// ```ignore
+/// struct SentryReporter;
+///
+/// impl ees::Reporter for SentryReporter {
+///     fn report(&self, error: ees::ErrorRef<'_>) {
+///         sentry::capture_error(error);
+///         // the process exits after the report, without dropping the guard
+///         // that would otherwise flush the event
+///         if let Some(client) = sentry::Hub::current().client() {
+///             client.flush(None);
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     let _guard = sentry::init("https://key@sentry.example.com/1");
+///     ees::set_reporter(SentryReporter);
+///     ees::run(app);
+/// }
+/// ```
+///
+/// Without the SDK, the `sentry` feature provides a `SentryReporter` that
+/// formats the events and leaves sending them to the application.
+///
+/// Errors created while one of the methods is running on the same thread are
+/// not reported again.
+pub trait Reporter: Send + Sync {
+    /// Called with the error of every reported [MainError](crate::MainError)
+    fn report(&self, error: crate::ErrorRef<'_>);
+
+    /// Called with every error created with [wrap!](crate::wrap), if
+    /// [report_wrapped](Self::report_wrapped) returns true
+    fn wrapped(&self, error: crate::ErrorRef<'_>) {
+        let _ = error;
+    }
+
+    /// Whether [wrapped](Self::wrapped) should be called (the default is
+    /// false)
+    fn report_wrapped(&self) -> bool {
+        false
+    }
+}
+
+static REPORTER: RwLock<Option<Box<dyn Reporter>>> = RwLock::new(None);
+// checked before taking the lock, so that `wrap!` stays cheap without a
+// reporter that wants to see wrapped errors
+static REPORT_WRAPPED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Install a [Reporter], replacing the previous one
+///
+/// ```
+/// struct Logger;
+///
+/// impl ees::Reporter for Logger {
+///     fn report(&self, error: ees::ErrorRef<'_>) {
+///         eprintln!("reporting: {}", ees::print_error_chain(error));
+///     }
+/// }
+///
+/// ees::set_reporter(Logger);
+/// ```
+pub fn set_reporter(reporter: impl Reporter + 'static) {
+    let report_wrapped = reporter.report_wrapped();
+    // a poisoned lock means that a reporter panicked, which doesn't affect
+    // replacing it
+    let mut current = REPORTER.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(Box::new(reporter));
+    REPORT_WRAPPED.store(report_wrapped, Ordering::Relaxed);
+}
+
+fn with_reporter(f: impl FnOnce(&dyn Reporter)) {
+    if REPORTING.with(|reporting| reporting.replace(true)) {
+        return;
+    }
+    if let Some(reporter) = &*REPORTER.read().unwrap_or_else(|e| e.into_inner()) {
+        f(reporter.as_ref());
+    }
+    REPORTING.with(|reporting| reporting.set(false));
+}
+
+/// Pass the error of a [MainError](crate::MainError) to the reporter
+pub(crate) fn report(error: crate::ErrorRef<'_>) {
+    with_reporter(|reporter| reporter.report(error));
+}
+
+/// Pass a newly created error to the reporter, if it wants to see them
+#[inline]
+pub(crate) fn wrapped(error: crate::ErrorRef<'_>) {
+    if REPORT_WRAPPED.load(Ordering::Relaxed) {
+        with_reporter(|reporter| reporter.wrapped(error));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fmt, io, thread, time::Duration};
+
+/// Decides how often and for which errors [retry] repeats an operation
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let policy = ees::RetryPolicy::new(5)
+///     .delay(Duration::from_millis(100))
+///     .backoff(2)
+///     .when(|error| ees::http_status(error) == Some(503));
+/// ```
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    attempts: u32,
+    delay: Duration,
+    backoff: u32,
+    transient: fn(crate::ErrorRef<'_>) -> bool,
+}
+
+impl RetryPolicy {
+    /// Make at most `attempts` attempts (at least one), without any delay in
+    /// between, retrying errors for which [is_transient] returns true
+    #[must_use]
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            delay: Duration::ZERO,
+            backoff: 1,
+            transient: is_transient,
+        }
+    }
+
+    /// Wait before the first retry (the default is no delay)
+    #[must_use]
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Multiply the delay by `factor` after every retry, e.g. `2` for
+    /// exponential backoff (the default is 1)
+    #[must_use]
+    pub fn backoff(mut self, factor: u32) -> Self {
+        self.backoff = factor;
+        self
+    }
+
+    /// Only retry errors for which `transient` returns true, replacing
+    /// [is_transient]
+    #[must_use]
+    pub fn when(mut self, transient: fn(crate::ErrorRef<'_>) -> bool) -> Self {
+        self.transient = transient;
+        self
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("attempts", &self.attempts)
+            .field("delay", &self.delay)
+            .field("backoff", &self.backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Run an operation, and repeat it as long as it fails with a transient error
+/// and the policy allows further attempts
+///
+/// Retrying stops at the first error that isn't transient. If the operation
+/// has been retried, the last error is wrapped with the number of attempts,
+/// e.g. `failed after 3 attempts: connection reset`. See also
+/// [retry!](crate::retry!) for operations that are written inline.
+///
+/// ```
+/// let mut attempts = 0;
+/// let result = ees::retry(ees::RetryPolicy::new(3), || {
+///     attempts += 1;
+///     if attempts < 3 {
+///         return Err(std::io::Error::from(std::io::ErrorKind::TimedOut));
+///     }
+///     Ok(attempts)
+/// });
+/// assert_eq!(result.unwrap(), 3);
+/// ```
+#[track_caller]
+pub fn retry<T, E: Into<crate::Error>>(
+    policy: RetryPolicy,
+    mut operation: impl FnMut() -> Result<T, E>,
+) -> crate::Result<T> {
+    let mut delay = policy.delay;
+    let mut attempt = 1;
+    loop {
+        let error = match operation() {
+            Ok(value) => return Ok(value),
+            Err(error) => error.into(),
+        };
+        if attempt == policy.attempts || !(policy.transient)(error.as_ref()) {
+            return Err(match attempt {
+                1 => error,
+                _ => crate::wrap!(error, "failed after {} attempts", attempt).into(),
+            });
+        }
+        if !delay.is_zero() {
+            thread::sleep(delay);
+            delay = delay.saturating_mul(policy.backoff);
+        }
+        attempt += 1;
+    }
+}
+
+/// Returns true if the error usually goes away when the operation is repeated
+///
+/// Errors with a [Kind](crate::Kind) are transient if it is `Transient`.
+/// Otherwise, this checks for an [io::Error] in the chain such as a timeout or
+/// a connection reset.
+#[must_use]
+pub fn is_transient(error: crate::ErrorRef<'_>) -> bool {
+    if let Some(kind) = crate::kind_of(error) {
+        return kind == crate::Kind::Transient;
+    }
+    crate::chain::static_chain(error)
+        .filter_map(|error| error.downcast_ref::<io::Error>())
+        .any(|error| {
+            matches!(
+                error.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::ConnectionRefused
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    #[test]
+    fn retry() {
+        let mut attempts = 0;
+        let result: crate::Result<()> = super::retry(super::RetryPolicy::new(3), || {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::ConnectionReset))
+        });
+        assert_eq!(attempts, 3);
+        assert_eq!(
+            crate::print_error_chain(result.unwrap_err().as_ref()).to_string(),
+            "failed after 3 attempts: connection reset"
+        );
+
+        let mut attempts = 0;
+        let result: crate::Result<()> = super::retry(super::RetryPolicy::new(3), || {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert_eq!(attempts, 1);
+        assert_eq!(result.unwrap_err().to_string(), "entity not found");
+
+        let mut attempts = 0;
+        let result: crate::Result<()> = super::retry(super::RetryPolicy::new(3), || {
+            attempts += 1;
+            match attempts {
+                1 => Err(io::Error::from(io::ErrorKind::TimedOut)),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        });
+        assert_eq!(attempts, 2);
+        assert_eq!(
+            crate::print_error_chain(result.unwrap_err().as_ref()).to_string(),
+            "failed after 2 attempts: entity not found"
+        );
+    }
+
+    fn flaky() -> crate::Result<()> {
+        Err(crate::err!(kind = crate::Kind::Transient, "flaky").into())
+    }
+
+    #[test]
+    fn retry_macro() {
+        let result = crate::retry!(super::RetryPolicy::new(2), flaky()?);
+        assert_eq!(
+            crate::print_error_chain(result.unwrap_err().as_ref()).to_string(),
+            "failed after 2 attempts: flaky"
+        );
+    }
+}
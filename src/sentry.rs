@@ -0,0 +1,121 @@
+use std::{fmt, time};
+
+/// A [Reporter](crate::Reporter) that formats errors as
+/// [Sentry](https://sentry.io) events
+///
+/// This doesn't depend on the Sentry SDK: the event is passed as a JSON
+/// string to a function supplied by the application, which sends it with the
+/// HTTP client or SDK it already uses.
+///
+/// ```
+/// ees::set_reporter(ees::SentryReporter::new(|event| {
+///     // e.g. POST the event to https://sentry.example.com/api/1/store/
+///     eprintln!("sending {}", event);
+/// }));
+/// ```
+///
+/// Every error in the chain becomes a value of the `exception` interface,
+/// with the root cause first, as Sentry expects:
+///
+/// ```json
+/// {
+///   "level": "error",
+///   "timestamp": 1791970245,
+///   "exception": {"values": [
+///     {"type": "std::io::Error", "value": "No such file or directory (os error 2)"},
+///     {"value": "failed to load app.toml"}
+///   ]}
+/// }
+/// ```
+///
+/// (The event is formatted on one line.) The type is only included if it is
+/// known, as with [ChainFormat::type_names](crate::ChainFormat::type_names),
+/// and the messages are masked by the registered [Redactor](crate::Redactor)s.
+pub struct SentryReporter {
+    send: Box<dyn Fn(&str) + Send + Sync>,
+}
+
+impl SentryReporter {
+    /// A reporter that passes every event to `send`
+    #[must_use]
+    pub fn new(send: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self {
+            send: Box::new(send),
+        }
+    }
+}
+
+impl fmt::Debug for SentryReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SentryReporter").finish_non_exhaustive()
+    }
+}
+
+impl crate::Reporter for SentryReporter {
+    fn report(&self, error: crate::ErrorRef<'_>) {
+        let event = Event {
+            error,
+            time: time::SystemTime::now(),
+        };
+        (self.send)(&event.to_string());
+    }
+}
+
+struct Event<'a> {
+    error: crate::ErrorRef<'a>,
+    time: time::SystemTime,
+}
+
+impl fmt::Display for Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // times before 1970 are clamped, the clock is most likely wrong
+        let timestamp = self
+            .time
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        write!(
+            f,
+            "{{\"level\":\"error\",\"timestamp\":{},\"exception\":{{\"values\":[",
+            timestamp
+        )?;
+        let type_names = crate::chain::TypeNames::new(&self.error);
+        let errors: Vec<_> = crate::Chain::new(self.error).collect();
+        for (i, error) in errors.into_iter().rev().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str("{")?;
+            if let Some(type_name) = type_names.of(error) {
+                f.write_str("\"type\":")?;
+                crate::internal::write_json_string(f, &type_name)?;
+                f.write_str(",")?;
+            }
+            f.write_str("\"value\":")?;
+            crate::internal::write_json_string(f, &crate::redact::Redacting(&error))?;
+            f.write_str("}")?;
+        }
+        f.write_str("]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn event() {
+        let e = std::io::Error::new(std::io::ErrorKind::NotFound, "no \"app.toml\"");
+        let e = crate::wrap!(e, "failed to load config");
+        let error: crate::Error = e.into();
+        let event = super::Event {
+            error: error.as_ref(),
+            time: UNIX_EPOCH + Duration::from_secs(1_791_970_245),
+        };
+        assert_eq!(
+            event.to_string(),
+            "{\"level\":\"error\",\"timestamp\":1791970245,\"exception\":{\"values\":[\
+             {\"type\":\"std::io::Error\",\"value\":\"no \\\"app.toml\\\"\"},\
+             {\"value\":\"failed to load config\"}]}}"
+        );
+    }
+}
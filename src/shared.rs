@@ -0,0 +1,87 @@
+use alloc::sync::Arc;
+use core::{error, fmt};
+
+/// A cloneable error, for fanning out a single failure to multiple waiting
+/// tasks
+///
+/// `SharedError` is a transparent wrapper: its message and sources are those
+/// of the wrapped error.
+///
+/// ```
+/// let error = ees::SharedError::new(ees::err!("connection lost"));
+/// let copy = error.clone();
+/// let owned: ees::Error = copy.into();
+/// assert_eq!(owned.to_string(), "connection lost");
+/// ```
+#[derive(Clone)]
+pub struct SharedError {
+    inner: Arc<dyn error::Error + Send + Sync + 'static>,
+}
+
+impl SharedError {
+    /// Wrap an error so that it can be cloned
+    pub fn new(error: impl Into<crate::Error>) -> Self {
+        Self {
+            inner: Arc::from(error.into()),
+        }
+    }
+
+    /// The wrapped error
+    #[must_use]
+    pub fn as_error(&self) -> crate::ErrorRef<'_> {
+        self.inner.as_ref()
+    }
+}
+
+impl fmt::Debug for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner.source()
+    }
+
+    #[allow(deprecated)]
+    fn cause(&self) -> Option<&dyn error::Error> {
+        self.inner.cause()
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.inner.provide(request);
+    }
+}
+
+impl From<crate::Error> for SharedError {
+    fn from(error: crate::Error) -> Self {
+        Self {
+            inner: Arc::from(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn shared_error() {
+        let e = crate::wrap!(crate::err!("root"), "outer");
+        let shared = crate::SharedError::new(e);
+        let clone = shared.clone();
+        assert_eq!(crate::print_error_chain(&clone).to_string(), "outer: root");
+        let owned: crate::Error = shared.into();
+        assert_eq!(
+            crate::print_error_chain(owned.as_ref()).to_string(),
+            "outer: root"
+        );
+        assert!(clone.as_error().source().is_some());
+    }
+}
@@ -0,0 +1,212 @@
+use alloc::string::{String, ToString};
+use core::{error, fmt, ops::Range};
+
+/// An error with the source text that caused it, e.g. a configuration file
+/// with a syntax error
+///
+/// `Snippet` is a transparent wrapper: its message and sources are those of
+/// the wrapped error. The `{:#}` format of
+/// [print_error_chain](crate::print_error_chain) and [MainError](crate::MainError)
+/// reports print the line containing the span after the causes, with the span
+/// underlined:
+///
+/// ```
+/// let text = "[server]\nport = \"abc\"\n";
+/// let e = ees::Snippet::new(ees::err!("invalid port"), text, 16..21)
+///     .with_name("config.toml")
+///     .with_label("expected an integer");
+/// assert_eq!(
+///     format!("{:#}", ees::print_error_chain(&e)),
+///     "invalid port\n\n \
+///      --> config.toml:2:8\n  \
+///       |\n\
+///      2 | port = \"abc\"\n  \
+///       |        ^^^^^ expected an integer"
+/// );
+/// ```
+///
+/// Spans are byte offsets into the text. A span that continues on the next
+/// line is underlined up to the end of its first line.
+pub struct Snippet(crate::report::View<State>);
+
+#[derive(Debug)]
+pub(crate) struct State {
+    pub(crate) error: crate::Error,
+    text: String,
+    span: Range<usize>,
+    name: Option<String>,
+    label: Option<String>,
+}
+
+impl Snippet {
+    /// Wrap an error, with the text it refers to and the span within the text
+    pub fn new(
+        error: impl Into<crate::Error>,
+        text: impl Into<String>,
+        span: Range<usize>,
+    ) -> Self {
+        Self(crate::report::View(State {
+            error: error.into(),
+            text: text.into(),
+            span,
+            name: None,
+            label: None,
+        }))
+    }
+
+    /// Set the name of the text, usually a file name
+    #[must_use]
+    pub fn with_name(mut self, name: impl fmt::Display) -> Self {
+        self.0 .0.name = Some(name.to_string());
+        self
+    }
+
+    /// Set a label, which is printed after the underlined span
+    #[must_use]
+    pub fn with_label(mut self, label: impl fmt::Display) -> Self {
+        self.0 .0.label = Some(label.to_string());
+        self
+    }
+
+    /// The text the span refers to
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.0 .0.text
+    }
+
+    /// The span within the text
+    #[must_use]
+    pub fn span(&self) -> Range<usize> {
+        self.0 .0.span.clone()
+    }
+
+    /// The wrapped error
+    #[must_use]
+    pub fn as_error(&self) -> crate::ErrorRef<'_> {
+        self.0 .0.error.as_ref()
+    }
+}
+
+impl State {
+    /// Write the snippet, after a line break
+    pub(crate) fn write(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = floor_char_boundary(&self.text, self.span.start);
+        let end = floor_char_boundary(&self.text, self.span.end.max(self.span.start));
+        let line_start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| start + i);
+        let line = self.text[line_start..line_end].trim_end_matches('\r');
+        let line_number = self.text[..start].matches('\n').count() + 1;
+        let column = self.text[line_start..start].chars().count();
+        let underline = self.text[start..end.min(line_start + line.len()).max(start)]
+            .chars()
+            .count()
+            .max(1);
+
+        let width = line_number.to_string().len();
+        write!(f, "\n{:width$}--> ", "", width = width)?;
+        if let Some(name) = &self.name {
+            write!(f, "{}:", name)?;
+        }
+        write!(f, "{}:{}", line_number, column + 1)?;
+        write!(f, "\n{:width$} |", "", width = width)?;
+        write!(f, "\n{} | {}", line_number, line)?;
+        write!(
+            f,
+            "\n{:width$} | {:column$}{:^<underline$}",
+            "",
+            "",
+            "",
+            width = width,
+            column = column,
+            underline = underline
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, " {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+impl fmt::Debug for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = &self.0 .0;
+        f.debug_struct("Snippet")
+            .field("error", &state.error)
+            .field("text", &state.text)
+            .field("span", &state.span)
+            .field("name", &state.name)
+            .field("label", &state.label)
+            .finish()
+    }
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0 .0.error.fmt(f)
+    }
+}
+
+impl error::Error for Snippet {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0 .0.error.source()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&self.0)
+    }
+
+    #[cfg(ees_nightly)]
+    fn provide<'a>(&'a self, request: &mut error::Request<'a>) {
+        self.0 .0.error.provide(request);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString};
+
+    #[test]
+    fn snippet() {
+        let e = super::Snippet::new(crate::err!("unexpected end"), "let x = ", 8..8);
+        let e = crate::Report::new(crate::wrap!(e, "parse failed")).with_note("in the REPL");
+        assert_eq!(e.to_string(), "parse failed");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "parse failed\n\nCaused by:\n    unexpected end\n\n \
+             --> 1:9\n  |\n1 | let x = \n  |         ^\n\nnote: in the REPL"
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let text = "a\r\n\n\n\n\n\n\n\n\nnaïve = über\r\nend";
+        let e = super::Snippet::new(crate::err!("invalid"), text, 20..100).with_label("here");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "invalid\n\n  --> 10:9\n   |\n10 | naïve = über\n   |         ^^^^ here"
+        );
+        // the start is inside of the 'ü'
+        let e = super::Snippet::new(crate::err!("invalid"), text, 21..22);
+        assert!(format!("{:#}", crate::print_error_chain(&e)).ends_with("|         ^"));
+
+        let e = super::Snippet::new(crate::wrap!(e, "outer"), "b", 0..1).with_name("b.txt");
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&e)),
+            "outer\n\nCaused by:\n    invalid\n\n \
+             --> b.txt:1:1\n  |\n1 | b\n  | ^\n\n  \
+             --> 10:9\n   |\n10 | naïve = über\n   |         ^"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use alloc::{string::String, string::ToString, sync::Arc};
+use core::{
+    error, fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// An error for unit tests of code that handles errors, which records whether
+/// it has been displayed or dropped
+///
+/// ```
+/// let error = ees::TestError::new("disk full").with_source(ees::err!("quota exceeded"));
+/// let probe = error.probe();
+/// let error: ees::Error = error.into();
+/// assert!(!probe.displayed());
+/// let _ = ees::print_error_chain(error.as_ref()).to_string();
+/// assert!(probe.displayed());
+/// drop(error);
+/// assert!(probe.dropped());
+/// ```
+pub struct TestError {
+    message: String,
+    source: Option<crate::Error>,
+    state: Arc<State>,
+}
+
+#[derive(Default)]
+struct State {
+    displayed: AtomicBool,
+    dropped: AtomicBool,
+}
+
+impl TestError {
+    /// Create an error with the given message and no source
+    pub fn new(message: impl fmt::Display) -> Self {
+        Self {
+            message: message.to_string(),
+            source: None,
+            state: Arc::default(),
+        }
+    }
+
+    /// Set the source of this error
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<crate::Error>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// A handle for checking what has happened to this error, which remains
+    /// usable after the error is dropped
+    #[must_use]
+    pub fn probe(&self) -> TestErrorProbe {
+        TestErrorProbe {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl fmt::Debug for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestError")
+            .field("message", &self.message)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.state.displayed.store(true, Ordering::Relaxed);
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for TestError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source.as_ref().map(|source| source.as_ref() as _)
+    }
+}
+
+impl Drop for TestError {
+    fn drop(&mut self) {
+        self.state.dropped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Records what has happened to a [TestError], see [TestError::probe]
+#[derive(Clone)]
+pub struct TestErrorProbe {
+    state: Arc<State>,
+}
+
+impl TestErrorProbe {
+    /// Whether the error has been displayed, e.g. when printing a chain that
+    /// contains it
+    #[must_use]
+    pub fn displayed(&self) -> bool {
+        self.state.displayed.load(Ordering::Relaxed)
+    }
+
+    /// Whether the error has been dropped
+    #[must_use]
+    pub fn dropped(&self) -> bool {
+        self.state.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for TestErrorProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestErrorProbe")
+            .field("displayed", &self.displayed())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn probe() {
+        let error = super::TestError::new("outer").with_source(crate::err!("root"));
+        let probe = error.probe();
+        let e = crate::wrap!(error, "wrapped");
+        assert_eq!(e.to_string(), "wrapped");
+        assert!(!probe.displayed());
+        assert_eq!(crate::chain_messages(&e), ["wrapped", "outer", "root"]);
+        assert!(probe.displayed());
+        assert!(!probe.dropped());
+        drop(e);
+        assert!(probe.dropped());
+    }
+}
@@ -0,0 +1,152 @@
+use std::{error, fmt, sync::RwLock};
+
+/// A table of user-facing messages for specific codes and types of errors,
+/// used by [MainError](crate::MainError) reports
+///
+/// The table is installed once with [set_translations], usually at the start
+/// of `main()`. If an error anywhere in the chain matches a rule, the report
+/// starts with the message of the rule, and the original chain follows in the
+/// "Caused by:" section. This keeps the phrasing for end users in one place,
+/// while the detail remains available for bug reports:
+///
+/// ```
+/// use ees::Translations;
+///
+/// ees::set_translations(
+///     Translations::new()
+///         .code("E0042", "The configuration file is incomplete.")
+///         .error::<std::io::Error>("Could not access a file."),
+/// );
+///
+/// let e = ees::err!(code = "E0042", "missing field `name`");
+/// let e = ees::MainError::from(ees::wrap!(e, "failed to load app.toml"));
+/// # #[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
+/// assert_eq!(
+///     format!("{:?}", e),
+///     "The configuration file is incomplete.\n\n\
+///      Caused by:\n    \
+///      0: failed to load app.toml\n    \
+///      1: missing field `name`"
+/// );
+/// ```
+///
+/// The errors are checked from the outermost error to the root cause, and
+/// for each error the first matching rule is used. Messages are only
+/// translated in the report: the error itself and [Reporter](crate::Reporter)s
+/// are unaffected.
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    rules: Vec<(Rule, String)>,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    Code(crate::ErrorCode),
+    Type(fn(crate::ErrorRef<'_>) -> bool),
+}
+
+fn is<E: error::Error + 'static>(error: crate::ErrorRef<'_>) -> bool {
+    error.is::<E>()
+}
+
+impl Translations {
+    /// An empty table
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `message` for errors with an [ErrorCode](crate::ErrorCode)
+    #[must_use]
+    pub fn code(mut self, code: impl Into<crate::ErrorCode>, message: impl Into<String>) -> Self {
+        self.rules.push((Rule::Code(code.into()), message.into()));
+        self
+    }
+
+    /// Use `message` for errors of type `E`
+    #[must_use]
+    pub fn error<E: error::Error + 'static>(mut self, message: impl Into<String>) -> Self {
+        self.rules.push((Rule::Type(is::<E>), message.into()));
+        self
+    }
+
+    /// The message for an error, if an error in its chain matches a rule
+    #[must_use]
+    pub fn lookup(&self, error: crate::ErrorRef<'_>) -> Option<&str> {
+        crate::chain::static_chain(error).find_map(|error| {
+            let (_, message) = self.rules.iter().find(|(rule, _)| match rule {
+                Rule::Code(code) => crate::internal::error_code(error).as_ref() == Some(code),
+                Rule::Type(is) => is(error),
+            })?;
+            Some(message.as_str())
+        })
+    }
+}
+
+static TRANSLATIONS: RwLock<Option<Translations>> = RwLock::new(None);
+
+/// Install [Translations], replacing the previous ones
+pub fn set_translations(translations: Translations) {
+    // a poisoned lock can't leave the table in an invalid state
+    let mut current = TRANSLATIONS.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(translations);
+}
+
+/// The error with the message from the installed [Translations], if any rule
+/// matches
+pub(crate) fn translate(error: crate::ErrorRef<'_>) -> Option<Translated<'_>> {
+    let translations = TRANSLATIONS.read().unwrap_or_else(|e| e.into_inner());
+    let message = translations.as_ref()?.lookup(error)?;
+    Some(Translated {
+        message: message.to_string(),
+        error,
+    })
+}
+
+/// A user-facing message, caused by the original error
+#[derive(Debug)]
+pub(crate) struct Translated<'a> {
+    message: String,
+    error: crate::ErrorRef<'a>,
+}
+
+impl fmt::Display for Translated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Translated<'_> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn lookup() {
+        let translations = super::Translations::new()
+            .error::<std::fmt::Error>("formatting failed")
+            .code(7_u32, "code 7")
+            .error::<std::io::Error>("io failed");
+        let e = crate::err!(code = 7_u32, "root");
+        let e = crate::wrap!(e, "outer");
+        assert_eq!(translations.lookup(&e), Some("code 7"));
+        // the outermost matching error is used, even if an earlier rule matches
+        // a cause
+        let e = std::io::Error::other(crate::wrap!(std::fmt::Error, "inner"));
+        assert_eq!(translations.lookup(&e), Some("io failed"));
+        let e = crate::err!(code = 8_u32, "root");
+        assert_eq!(translations.lookup(&e), None);
+
+        let translated = super::Translated {
+            message: "friendly".to_string(),
+            error: &e,
+        };
+        assert_eq!(
+            format!("{:#}", crate::print_error_chain(&translated)),
+            "friendly\n\nCaused by:\n    root"
+        );
+    }
+}
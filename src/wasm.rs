@@ -0,0 +1,67 @@
+//! Reporting errors and panics on `wasm32` targets
+//!
+//! In the browser, nothing written to stderr is visible, and a panic only
+//! shows up as an `unreachable` trap. The functions in this module write
+//! reports to a console function instead, which is set with [set_console].
+//! This crate doesn't depend on `wasm-bindgen`, so the console function is a
+//! one-liner in the application, e.g. with `web-sys`:
+//!
+//! ```ignore
+//! fn console_error(message: &str) {
+//!     web_sys::console::error_1(&message.into());
+//! }
+//!
+//! #[wasm_bindgen::prelude::wasm_bindgen(start)]
+//! fn start() {
+//!     ees::wasm::set_console(console_error);
+//!     ees::wasm::install_panic_hook();
+//! }
+//! ```
+//!
+//! Without a console function, reports are written to stderr, which works on
+//! WASI.
+
+use std::sync::RwLock;
+
+/// A function that writes a message to the console, e.g. `console.error`
+pub type Console = fn(&str);
+
+static CONSOLE: RwLock<Option<Console>> = RwLock::new(None);
+
+/// Set the function that reports are written to, replacing the previous one
+pub fn set_console(console: Console) {
+    // the console is a plain function pointer, so a poisoned lock holds valid data
+    let mut current = CONSOLE.write().unwrap_or_else(|e| e.into_inner());
+    *current = Some(console);
+}
+
+fn write(message: &str) {
+    match *CONSOLE.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(console) => console(message),
+        None => eprintln!("{}", message),
+    }
+}
+
+/// Write the same report as [MainError](crate::MainError) to the console
+///
+/// ```ignore
+/// #[wasm_bindgen::prelude::wasm_bindgen]
+/// pub fn run() {
+///     if let Err(e) = do_work() {
+///         ees::wasm::report(e);
+///     }
+/// }
+/// ```
+pub fn report(error: impl Into<crate::MainError>) {
+    let error = error.into();
+    crate::reporter::report(error.as_error());
+    write(&error.full_report());
+}
+
+/// Like [install_panic_hook](crate::install_panic_hook), writing the panic
+/// report to the console
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        crate::panic_hook::report_panic(info, |report| write(&report.to_string()))
+    }));
+}
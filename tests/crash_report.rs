@@ -0,0 +1,20 @@
+#![cfg(feature = "std")]
+
+// The crash report path is global, so it's tested in its own process
+
+use std::process::Termination;
+
+#[test]
+fn crash_report() {
+    let path = std::env::temp_dir().join(format!("ees-crash-report-{}.json", std::process::id()));
+    ees::set_crash_report_path(&path);
+    let e = ees::wrap!(ees::err!(code = "E0042", "missing field"), "invalid config");
+    let _ = ees::MainError::from(e).report();
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(report.starts_with(
+        "{\"messages\":[\"invalid config\",\"missing field\"],\"codes\":[\"E0042\"],\
+         \"severity\":\"error\",\"exit_code\":1,\"location\":{\"file\":\"tests/crash_report.rs\","
+    ));
+    assert!(report.ends_with("}\n"));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "std")]
+
+// The creation hook is global, so it's tested in its own process
+
+use std::panic::Location;
+use std::sync::Mutex;
+
+static CREATED: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+fn record(message: &str, location: &'static Location<'static>) {
+    assert_eq!(location.file(), file!());
+    CREATED
+        .lock()
+        .unwrap()
+        .push((message.to_string(), location.line()));
+}
+
+#[test]
+fn creation_hook() {
+    let _ = ees::err!("before");
+    ees::set_creation_hook(record);
+    let (e, line) = (ees::err!("root {}", 1), line!());
+    let _ = ees::wrap!(e, kind = ees::Kind::User, "outer");
+    let created = CREATED.lock().unwrap();
+    assert_eq!(
+        *created,
+        [
+            ("root 1".to_string(), line),
+            ("outer".to_string(), line + 1)
+        ]
+    );
+}
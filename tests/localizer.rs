@@ -0,0 +1,36 @@
+#![cfg(feature = "std")]
+
+// The localizer is global, so it's tested in its own process
+
+fn localize(message: &str, code: Option<&str>) -> Option<String> {
+    match (message, code) {
+        (_, Some("E1")) => Some("Datei nicht gefunden".to_string()),
+        ("outer", None) => Some(format!("äußerer Fehler\n{}", message)),
+        _ => None,
+    }
+}
+
+#[test]
+fn localizer() {
+    let e = ees::wrap!(ees::err!(code = "E1", "file not found"), "outer"; path = "a.txt");
+    let e = ees::Report::new(e).with_note("a note");
+    assert_eq!(
+        ees::print_error_chain(&e).to_string(),
+        "outer: file not found"
+    );
+    ees::set_localizer(localize);
+    assert_eq!(
+        ees::print_error_chain(&e).to_string(),
+        "äußerer Fehler\nouter: Datei nicht gefunden"
+    );
+    assert_eq!(
+        format!("{:#}", ees::print_error_chain(&e)),
+        "äußerer Fehler\nouter (path = a.txt)\n\nCaused by:\n    Datei nicht gefunden\n\nnote: a note"
+    );
+    assert_eq!(
+        ees::print_error_chain(&e).causes().to_string(),
+        "Datei nicht gefunden"
+    );
+    // the messages of the errors themselves are unchanged
+    assert_eq!(e.to_string(), "outer");
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "std")]
+
+// Redactors are global, so they're tested in their own process
+
+#[test]
+fn redactors() {
+    ees::add_redactor(ees::Redactor::key("token"));
+    ees::add_redactor(ees::Redactor::value("hunter2"));
+    let e = ees::err!("wrong password hunter2");
+    let e = ees::wrap!(e, "login failed"; token = "abc");
+    assert_eq!(
+        format!("{:#}", ees::print_error_chain(&e)),
+        "login failed (token = [REDACTED])\n\nCaused by:\n    wrong password [REDACTED]"
+    );
+    assert_eq!(
+        ees::print_error_chain_json(&e).to_string(),
+        r#"[{"message":"login failed"},{"message":"wrong password [REDACTED]"}]"#
+    );
+    assert!(!ees::debug_error_chain(&e).to_string().contains("hunter2"));
+    assert!(!format!("{:?}", ees::MainError::from(e)).contains("hunter2"));
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "std")]
+
+// The report hook is global, so it's tested in its own process
+
+use std::fmt;
+
+fn report(error: ees::ErrorRef<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} (version 1.2.3)", ees::print_error_chain(error))
+}
+
+#[cfg(not(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics")))]
+#[test]
+fn report_hook() {
+    let e = ees::MainError::from(ees::wrap!(ees::err!("root"), "outer"));
+    assert_eq!(format!("{:?}", e), "outer\n\nCaused by:\n    root");
+    ees::set_report_hook(report);
+    assert_eq!(format!("{:?}", e), "outer: root (version 1.2.3)");
+    assert_eq!(e.to_string(), "outer: root (version 1.2.3)");
+}
+
+#[cfg(any(feature = "timestamps", feature = "error-ids", feature = "diagnostics"))]
+#[test]
+fn report_hook_framed() {
+    let e = ees::MainError::from(ees::wrap!(ees::err!("root"), "outer"));
+    ees::set_report_hook(report);
+    assert!(format!("{:?}", e).contains("outer: root (version 1.2.3)"));
+    assert_eq!(format!("{:#?}", e), "outer: root (version 1.2.3)");
+}
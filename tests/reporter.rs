@@ -0,0 +1,37 @@
+#![cfg(feature = "std")]
+
+// The reporter is global, so it's tested in its own process
+
+use std::process::Termination;
+use std::sync::Mutex;
+
+static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Recorder;
+
+impl ees::Reporter for Recorder {
+    fn report(&self, error: ees::ErrorRef<'_>) {
+        let chain = ees::print_error_chain(error).to_string();
+        REPORTED.lock().unwrap().push(format!("report: {}", chain));
+    }
+
+    fn wrapped(&self, error: ees::ErrorRef<'_>) {
+        // errors created here are not reported again
+        let _ = ees::wrap!(ees::err!("nested"), "ignored");
+        REPORTED.lock().unwrap().push(format!("wrapped: {}", error));
+    }
+
+    fn report_wrapped(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn reporter() {
+    let _ = ees::wrap!(ees::err!("root"), "before");
+    ees::set_reporter(Recorder);
+    let e = ees::wrap!(ees::err!("root"), "outer");
+    let _ = ees::MainError::from(e).report();
+    let reported = REPORTED.lock().unwrap();
+    assert_eq!(*reported, ["wrapped: outer", "report: outer: root"]);
+}
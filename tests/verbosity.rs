@@ -0,0 +1,22 @@
+#![cfg(feature = "std")]
+
+// Environment variables are global, so verbosity is tested in its own process
+
+#[test]
+fn verbosity_from_env() {
+    let (e, line) = (ees::wrap!(ees::err!("root"), "failed"), line!());
+    let e = ees::MainError::from(e);
+    assert_eq!(e.to_string(), "failed\n\nCaused by:\n    root");
+    std::env::set_var("EES_VERBOSE", "0");
+    assert_eq!(e.to_string(), "failed: root");
+    std::env::set_var("EES_VERBOSE", "1");
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "failed ({0}:{1})\n\nCaused by:\n    root ({0}:{1})",
+            file!(),
+            line
+        )
+    );
+    std::env::remove_var("EES_VERBOSE");
+}